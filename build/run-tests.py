@@ -7,6 +7,9 @@ import argparse
 
 if __name__ == "__main__":
     F16_PIXEL_TYPE = ["f16-pixel-type"]
+    SERDE = ["serde"]
+    MACROS = ["macros"]
+    STREAM = ["stream"]
 
     R73_COMPAT_FEATURE = "vsscript-r73-compat"
     R73_COMPAT = [R73_COMPAT_FEATURE]
@@ -18,6 +21,9 @@ if __name__ == "__main__":
 
     features = [
         F16_PIXEL_TYPE,
+        SERDE,
+        MACROS,
+        STREAM,
         R73_COMPAT
     ]
 
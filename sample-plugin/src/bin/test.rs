@@ -2,6 +2,7 @@
 #[macro_use]
 extern crate cfg_if;
 extern crate vapoursynth;
+use vapoursynth::plugins::Version;
 use vapoursynth::prelude::*;
 use vapoursynth::video_info::Framerate;
 
@@ -187,10 +188,75 @@ fn test_arguments() {
     println!(" ok");
 }
 
+fn test_clip_info() {
+    print!("Running test_clip_info()...");
+    stdout().flush().unwrap();
+
+    let mut env = make_environment();
+    env.eval_file("test-vpy/random_noise.vpy", EvalFlags::Nothing)
+        .unwrap();
+    let node = env.get_output(0).unwrap().0;
+
+    let core = env.get_core().unwrap();
+    let plugin = core
+        .get_plugin_by_id("com.example.vapoursynth-rs")
+        .unwrap()
+        .unwrap();
+
+    let mut map = OwnedMap::new(API::get().unwrap());
+    map.set_node("clip", &node).unwrap();
+    let rv = plugin.invoke("ClipInfo", &map).unwrap();
+    assert_eq!(rv.error(), None);
+    assert_eq!(rv.get_int("num_frames"), Ok(10));
+    assert_eq!(rv.get_int("width"), Ok(320));
+    assert_eq!(rv.get_int("height"), Ok(240));
+
+    assert_eq!(plugin.version(), Version::parse(env!("CARGO_PKG_VERSION")));
+
+    println!(" ok");
+}
+
+fn test_panic() {
+    print!("Running test_panic()...");
+    stdout().flush().unwrap();
+
+    let mut env = make_environment();
+    env.eval_file("test-vpy/random_noise.vpy", EvalFlags::Nothing)
+        .unwrap();
+    let node = env.get_output(0).unwrap().0;
+
+    let core = env.get_core().unwrap();
+    let plugin = core
+        .get_plugin_by_id("com.example.vapoursynth-rs")
+        .unwrap()
+        .unwrap();
+
+    let mut map = OwnedMap::new(API::get().unwrap());
+    map.set_node("clip", &node).unwrap();
+    map.set_int("frame", 1).unwrap();
+    let rv = plugin.invoke("Panic", &map).unwrap();
+    assert_eq!(rv.error(), None);
+    let node = rv.get_video_node("clip").unwrap();
+
+    assert!(node.get_frame(0).is_ok());
+
+    let error = node.get_frame(1).unwrap_err().to_string();
+    assert!(error.contains("panicked while getting frame 1"));
+    assert!(error.contains("requested the panicking frame"));
+
+    // The filter is poisoned after panicking.
+    let error = node.get_frame(2).unwrap_err().to_string();
+    assert!(error.contains("the filter has panicked earlier"));
+
+    println!(" ok");
+}
+
 fn main() {
     test_passthrough();
     test_invert();
     test_random_noise();
     test_make_random_noise();
     test_arguments();
+    test_clip_info();
+    test_panic();
 }
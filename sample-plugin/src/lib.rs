@@ -15,7 +15,7 @@ use vapoursynth::function::Function;
 use vapoursynth::map::ValueIter;
 use vapoursynth::plugins::*;
 use vapoursynth::prelude::*;
-use vapoursynth::video_info::{Framerate, Resolution, VideoInfo};
+use vapoursynth::video_info::{Framerate, Property, Resolution, VideoInfo};
 
 const PLUGIN_IDENTIFIER: &str = "com.example.vapoursynth-rs";
 
@@ -431,6 +431,95 @@ make_filter_function! {
     }
 }
 
+// A filter which panics when producing the given frame.
+struct Panic<'core> {
+    clip: Node<'core>,
+    frame: usize,
+}
+
+impl<'core> Filter<'core> for Panic<'core> {
+    fn video_info(&self, _api: API, _core: CoreRef<'core>) -> Vec<VideoInfo<'core>> {
+        vec![self.clip.info()]
+    }
+
+    fn get_frame_initial(
+        &self,
+        _api: API,
+        _core: CoreRef<'core>,
+        context: FrameContext,
+        n: usize,
+    ) -> Result<Option<FrameRef<'core>>, Error> {
+        self.clip.request_frame_filter(context, n);
+        Ok(None)
+    }
+
+    fn get_frame(
+        &self,
+        _api: API,
+        _core: CoreRef<'core>,
+        context: FrameContext,
+        n: usize,
+    ) -> Result<FrameRef<'core>, Error> {
+        assert_ne!(n, self.frame, "requested the panicking frame");
+
+        self.clip
+            .get_frame_filter(context, n)
+            .ok_or_else(|| anyhow!("Couldn't get the source frame"))
+    }
+}
+
+make_filter_function! {
+    PanicFunction, "Panic"
+
+    fn create_panic<'core>(
+        _api: API,
+        _core: CoreRef<'core>,
+        clip: Node<'core>,
+        frame: usize,
+    ) -> Result<Option<Box<dyn Filter<'core> + 'core>>, Error> {
+        Ok(Some(Box::new(Panic { clip, frame })))
+    }
+}
+
+// A function which isn't a filter and returns information about a clip.
+struct ClipInfoFunction;
+
+impl FilterFunction for ClipInfoFunction {
+    fn name(&self) -> &str {
+        "ClipInfo"
+    }
+
+    fn args(&self) -> &str {
+        "clip:vnode;"
+    }
+
+    fn return_type(&self) -> &str {
+        "num_frames:int;width:int;height:int;"
+    }
+
+    fn call<'core>(
+        &self,
+        _api: API,
+        _core: CoreRef<'core>,
+        args: &Map<'core>,
+        out: &mut Map<'core>,
+    ) -> Result<(), Error> {
+        let info = args.get_video_node("clip")?.info();
+
+        // Variable resolution is reported as zero, like VapourSynth does.
+        let (width, height) = match info.resolution {
+            Property::Constant(Resolution { width, height }) => (width, height),
+            Property::Variable => (0, 0),
+        };
+
+        out.set_int("num_frames", info.num_frames as i64)?;
+        out.set_int("width", width as i64)?;
+        out.set_int("height", height as i64)?;
+
+        Ok(())
+    }
+}
+
 export_vapoursynth_plugin! {
     Metadata {
         identifier: PLUGIN_IDENTIFIER,
@@ -444,5 +533,7 @@ export_vapoursynth_plugin! {
         RandomNoiseFunction::new(),
         MakeRandomNoiseFunction::new(),
         ArgumentTestFilterFunction::new(),
+        PanicFunction::new(),
+        ClipInfoFunction,
     ]
 }
@@ -0,0 +1,201 @@
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::{Data, DeriveInput, Expr, Fields, GenericParam, Lifetime, LitStr};
+
+use crate::utils::{Errors, ident_to_lit, validate_name};
+
+/// A struct field holding a filter argument.
+struct Field<'a> {
+    ident: &'a syn::Ident,
+    ty: &'a syn::Type,
+    key: LitStr,
+    default: Option<Expr>,
+    range: Option<Expr>,
+}
+
+/// Parses the `#[filter_args(...)]` attributes of a field.
+fn parse_field(field: &syn::Field) -> syn::Result<Field<'_>> {
+    let ident = field.ident.as_ref().unwrap();
+    let mut key = None;
+    let mut default = None;
+    let mut range = None;
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("filter_args"))
+    {
+        attr.parse_nested_meta(|meta| {
+            let target = if meta.path.is_ident("rename") {
+                key = Some(meta.value()?.parse::<LitStr>()?);
+                return Ok(());
+            } else if meta.path.is_ident("default") {
+                &mut default
+            } else if meta.path.is_ident("range") {
+                &mut range
+            } else {
+                return Err(
+                    meta.error("unsupported attribute, expected `rename`, `default` or `range`")
+                );
+            };
+
+            if target.is_some() {
+                return Err(meta.error("duplicate attribute"));
+            }
+            *target = Some(meta.value()?.parse::<Expr>()?);
+            Ok(())
+        })?;
+    }
+
+    let key = key.unwrap_or_else(|| ident_to_lit(ident));
+    validate_name(&key, "argument name")?;
+
+    Ok(Field {
+        ident,
+        ty: &field.ty,
+        key,
+        default,
+        range,
+    })
+}
+
+pub(crate) fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &data.fields,
+                    "FilterArgs can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "FilterArgs can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut errors = Errors::default();
+
+    // The argument string is cached in a static, so it can't depend on type parameters.
+    let mut lifetimes = Vec::new();
+    for param in &input.generics.params {
+        match param {
+            GenericParam::Lifetime(param) => lifetimes.push(&param.lifetime),
+            _ => errors.push(syn::Error::new_spanned(
+                param,
+                "FilterArgs can't be derived for structs with type or const parameters",
+            )),
+        }
+    }
+    if let Some(lifetime) = lifetimes.get(1) {
+        errors.push(syn::Error::new_spanned(
+            lifetime,
+            "FilterArgs can only be derived for structs with at most one lifetime parameter",
+        ));
+    }
+
+    let mut parsed: Vec<Field> = Vec::with_capacity(fields.len());
+    for field in fields {
+        let field = match parse_field(field) {
+            Ok(field) => field,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        if let Some(other) = parsed.iter().find(|f| f.key.value() == field.key.value()) {
+            errors.push(syn::Error::new(
+                field.key.span(),
+                format!("duplicate argument name `{}`", field.key.value()),
+            ));
+            errors.push(syn::Error::new(
+                other.key.span(),
+                "the name is first used here",
+            ));
+        }
+
+        parsed.push(field);
+    }
+
+    errors.finish()?;
+    let fields = parsed;
+
+    let name = &input.ident;
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (impl_generics, lifetime) = match lifetimes.first() {
+        Some(&lifetime) => (quote!(<#lifetime>), lifetime.clone()),
+        None => {
+            let lifetime = Lifetime::new("'core", proc_macro2::Span::call_site());
+            (quote!(<#lifetime>), lifetime)
+        }
+    };
+
+    let push_args = fields.iter().map(|field| {
+        let Field { ty, key, .. } = field;
+        let has_default = field.default.is_some();
+        quote! {
+            ::vapoursynth::plugins::push_parameter::<#ty>(&mut args, #key, #has_default);
+        }
+    });
+
+    let values = fields.iter().map(|field| {
+        let Field { ident, ty, key, .. } = field;
+
+        let get = match &field.default {
+            Some(default) => quote! {
+                match ::vapoursynth::plugins::get_parameter::<#ty>(map, #key)? {
+                    ::std::option::Option::Some(value) => value,
+                    ::std::option::Option::None => #default,
+                }
+            },
+            None => quote! {
+                <#ty as ::vapoursynth::plugins::FilterParameter>::get_from_map(map, #key)?
+            },
+        };
+
+        let check_range = field.range.as_ref().map(|range| {
+            let range_str = range.to_token_stream().to_string();
+            quote! {
+                ::vapoursynth::plugins::check_range(&value, #range, #key, #range_str)?;
+            }
+        });
+
+        quote! {
+            #ident: {
+                let value: #ty = #get;
+                #check_range
+                value
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::vapoursynth::plugins::FilterArgs<#lifetime> for #name #ty_generics
+        #where_clause
+        {
+            fn args() -> &'static str {
+                static ARGS: ::std::sync::OnceLock<::std::string::String> =
+                    ::std::sync::OnceLock::new();
+
+                ARGS.get_or_init(|| {
+                    let mut args = ::std::string::String::new();
+                    #(#push_args)*
+                    args
+                })
+            }
+
+            fn from_map(
+                map: &::vapoursynth::map::Map<#lifetime>,
+            ) -> ::std::result::Result<Self, ::vapoursynth::plugins::ArgumentError> {
+                ::std::result::Result::Ok(Self {
+                    #(#values,)*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,125 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr};
+
+use crate::utils::{Errors, ident_to_lit, validate_key};
+
+/// A struct field stored in a frame property.
+struct Field<'a> {
+    ident: &'a syn::Ident,
+    key: LitStr,
+}
+
+/// Parses the `#[frame_props(...)]` attributes of a field.
+fn parse_field(field: &syn::Field) -> syn::Result<Field<'_>> {
+    let ident = field.ident.as_ref().unwrap();
+    let mut key = None;
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("frame_props"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                if key.is_some() {
+                    return Err(meta.error("duplicate `rename` attribute"));
+                }
+                key = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported attribute, expected `rename`"))
+            }
+        })?;
+    }
+
+    let key = key.unwrap_or_else(|| ident_to_lit(ident));
+    validate_key(&key)?;
+
+    Ok(Field { ident, key })
+}
+
+pub(crate) fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &data.fields,
+                    "FrameProps can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "FrameProps can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut errors = Errors::default();
+
+    let mut parsed: Vec<Field> = Vec::with_capacity(fields.len());
+    for field in fields {
+        let field = match parse_field(field) {
+            Ok(field) => field,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        if let Some(other) = parsed.iter().find(|f| f.key.value() == field.key.value()) {
+            errors.push(syn::Error::new(
+                field.key.span(),
+                format!("duplicate frame property key `{}`", field.key.value()),
+            ));
+            errors.push(syn::Error::new(
+                other.key.span(),
+                "the key is first used here",
+            ));
+        }
+
+        parsed.push(field);
+    }
+
+    errors.finish()?;
+    let fields = parsed;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let reads = fields.iter().map(|Field { ident, key }| {
+        quote! {
+            #ident: ::vapoursynth::frame_props::PropValue::read_prop(map, #key)?
+        }
+    });
+    let writes = fields.iter().map(|Field { ident, key }| {
+        quote! {
+            ::vapoursynth::frame_props::PropValue::write_prop(&self.#ident, map, #key)?;
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::vapoursynth::frame_props::FrameProps for #name #ty_generics
+        #where_clause
+        {
+            fn read_from(
+                map: &::vapoursynth::map::Map,
+            ) -> ::vapoursynth::frame_props::Result<Self> {
+                ::std::result::Result::Ok(Self {
+                    #(#reads,)*
+                })
+            }
+
+            fn write_to(
+                &self,
+                map: &mut ::vapoursynth::map::Map,
+            ) -> ::vapoursynth::frame_props::Result<()> {
+                #(#writes)*
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
@@ -0,0 +1,96 @@
+//! Procedural macros for the [`vapoursynth`](https://docs.rs/vapoursynth) crate.
+//!
+//! Don't depend on this crate directly, enable the `macros` feature of `vapoursynth` instead. The
+//! macros are re-exported and documented there.
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, parse_macro_input};
+
+mod filter_args;
+mod frame_props;
+mod plugin;
+mod utils;
+
+/// Derives `vapoursynth::frame_props::FrameProps`.
+#[proc_macro_derive(FrameProps, attributes(frame_props))]
+pub fn derive_frame_props(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    frame_props::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `vapoursynth::plugins::FilterArgs`.
+#[proc_macro_derive(FilterArgs, attributes(filter_args))]
+pub fn derive_filter_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    filter_args::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Turns a filter creation function into a type implementing
+/// `vapoursynth::plugins::FilterFunction`.
+///
+/// The attribute takes the filter name, `name = "FilterName"`, and optionally the name of the
+/// generated type, `type_name = TypeName`, which defaults to the filter name suffixed with
+/// `Function`. The generated type has a `new()` constructor.
+///
+/// Parameters of type `API` and `CoreRef` receive the corresponding values. All other parameters
+/// are filter arguments named after the parameter, of types implementing
+/// `vapoursynth::plugins::FilterParameter`. The function can have at most one lifetime
+/// parameter, the core lifetime.
+///
+/// # Example
+/// ```ignore
+/// #[vapoursynth::filter_function(name = "Invert")]
+/// fn create_invert<'core>(
+///     _api: API,
+///     _core: CoreRef<'core>,
+///     clip: Node<'core>,
+///     strength: Option<f64>,
+/// ) -> Result<Option<Box<dyn Filter<'core> + 'core>>, Error> {
+///     Ok(Some(Box::new(Invert { source: clip })))
+/// }
+///
+/// // Generates `InvertFunction` with the argument string `clip:vnode;strength:float:opt;`.
+/// ```
+#[proc_macro_attribute]
+pub fn filter_function(args: TokenStream, item: TokenStream) -> TokenStream {
+    plugin::filter_function(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Exports a VapourSynth plugin with the filter functions defined in the annotated module.
+///
+/// This replaces `export_vapoursynth_plugin!` and should be used only once in a `cdylib` crate.
+/// The attribute takes the plugin metadata: `identifier`, `namespace`, `name` and optionally
+/// `read_only`, which defaults to `false`. All functions in the module annotated with
+/// `#[filter_function]` are exported. Additional `FilterFunction` values can be listed in
+/// `functions = [...]`; these expressions are evaluated inside the module. The optional `version`
+/// and `required_api_version` take `Version` expressions and are passed to
+/// `export_vapoursynth_plugin!`, which defaults them to the crate version and the API version
+/// `vapoursynth` was built against.
+///
+/// # Example
+/// ```ignore
+/// #[vapoursynth::plugin(
+///     identifier = "com.example.invert",
+///     namespace = "invert",
+///     name = "Invert Example Plugin",
+///     functions = [super::OtherFunction::new()],
+/// )]
+/// mod plugin {
+///     #[vapoursynth::filter_function(name = "Invert")]
+///     fn create_invert<'core>(/* ... */) -> Result<Option<Box<dyn Filter<'core> + 'core>>, Error> {
+///         /* ... */
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn plugin(args: TokenStream, item: TokenStream) -> TokenStream {
+    plugin::plugin(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
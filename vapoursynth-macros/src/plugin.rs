@@ -0,0 +1,443 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::meta::ParseNestedMeta;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Expr, FnArg, GenericParam, Ident, Item, ItemFn, ItemMod, LitBool, LitStr, Pat,
+    Token, Type,
+};
+
+use crate::utils::{Errors, ident_to_lit, validate_name};
+
+/// Arguments of the `filter_function` attribute.
+struct FilterFunctionArgs {
+    name: LitStr,
+    type_name: Ident,
+}
+
+impl FilterFunctionArgs {
+    /// Parses the attribute arguments.
+    fn parse(args: TokenStream, span: Span) -> syn::Result<Self> {
+        let mut name = None;
+        let mut type_name = None;
+
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("name") {
+                set_once(&meta, &mut name, |meta| meta.value()?.parse::<LitStr>())
+            } else if meta.path.is_ident("type_name") {
+                set_once(&meta, &mut type_name, |meta| meta.value()?.parse::<Ident>())
+            } else {
+                Err(meta.error("unsupported argument, expected `name` or `type_name`"))
+            }
+        });
+        parser.parse2(args)?;
+
+        let name = name.ok_or_else(|| {
+            syn::Error::new(span, "missing filter name, add `name = \"FilterName\"`")
+        })?;
+        validate_name(&name, "filter name")?;
+
+        // Default to the naming convention of `make_filter_function!` users: "Invert" is exported
+        // by `InvertFunction`.
+        let type_name = type_name
+            .unwrap_or_else(|| format_ident!("{}Function", name.value(), span = name.span()));
+
+        Ok(Self { name, type_name })
+    }
+
+    /// Parses the arguments of a `filter_function` attribute found on an item.
+    fn from_attribute(attr: &Attribute) -> syn::Result<Self> {
+        let args = match &attr.meta {
+            syn::Meta::List(list) => list.tokens.clone(),
+            _ => TokenStream::new(),
+        };
+        Self::parse(args, attr.span())
+    }
+}
+
+/// Parses the value with `f`, or returns an error if it was already set.
+fn set_once<T>(
+    meta: &ParseNestedMeta,
+    value: &mut Option<T>,
+    f: impl FnOnce(&ParseNestedMeta) -> syn::Result<T>,
+) -> syn::Result<()> {
+    if value.is_some() {
+        return Err(meta.error("duplicate argument"));
+    }
+    *value = Some(f(meta)?);
+    Ok(())
+}
+
+/// Returns whether the attribute is a `filter_function` attribute.
+fn is_filter_function_attribute(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|s| s.ident == "filter_function")
+}
+
+/// Returns the last path segment identifier of the type.
+fn type_ident(ty: &Type) -> Option<&Ident> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last().map(|s| &s.ident),
+        _ => None,
+    }
+}
+
+/// A parameter of the filter creation function.
+enum Parameter<'a> {
+    Api,
+    Core,
+    Argument { name: LitStr, ty: &'a Type },
+}
+
+/// Classifies a parameter of the filter creation function.
+fn parse_parameter(arg: &FnArg) -> syn::Result<Parameter<'_>> {
+    let arg = match arg {
+        FnArg::Receiver(receiver) => {
+            return Err(syn::Error::new_spanned(
+                receiver,
+                "filter functions can't take `self`",
+            ));
+        }
+        FnArg::Typed(arg) => arg,
+    };
+
+    match type_ident(&arg.ty) {
+        Some(ident) if ident == "API" => return Ok(Parameter::Api),
+        Some(ident) if ident == "CoreRef" => return Ok(Parameter::Core),
+        _ => {}
+    }
+
+    let ident = match &*arg.pat {
+        Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => &pat.ident,
+        pat => {
+            return Err(syn::Error::new_spanned(
+                pat,
+                "filter arguments must be plain identifiers, which are used as argument names",
+            ));
+        }
+    };
+
+    let name = ident_to_lit(ident);
+    validate_name(&name, "argument name")?;
+
+    Ok(Parameter::Argument { name, ty: &arg.ty })
+}
+
+pub(crate) fn filter_function(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let args = FilterFunctionArgs::parse(args, Span::call_site());
+    let function: ItemFn = syn::parse2(item)?;
+
+    let mut errors = Errors::default();
+    let args = args.map_err(|e| errors.push(e)).ok();
+
+    let mut lifetimes = Vec::new();
+    for param in &function.sig.generics.params {
+        match param {
+            GenericParam::Lifetime(param) => lifetimes.push(&param.lifetime),
+            _ => errors.push(syn::Error::new_spanned(
+                param,
+                "filter functions can't have type or const parameters",
+            )),
+        }
+    }
+    if let Some(lifetime) = lifetimes.get(1) {
+        errors.push(syn::Error::new_spanned(
+            lifetime,
+            "filter functions can have at most one lifetime parameter, the core lifetime",
+        ));
+    }
+    if let Some(asyncness) = &function.sig.asyncness {
+        errors.push(syn::Error::new_spanned(
+            asyncness,
+            "filter functions can't be async",
+        ));
+    }
+
+    let mut parameters = Vec::new();
+    for arg in &function.sig.inputs {
+        match parse_parameter(arg) {
+            Ok(parameter) => parameters.push(parameter),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    for (i, parameter) in parameters.iter().enumerate() {
+        if let Parameter::Argument { name, .. } = parameter {
+            let duplicate = parameters[..i].iter().any(|p| {
+                matches!(p, Parameter::Argument { name: other, .. } if other.value() == name.value())
+            });
+            if duplicate {
+                errors.push(syn::Error::new(
+                    name.span(),
+                    format!("duplicate argument name `{}`", name.value()),
+                ));
+            }
+        }
+    }
+
+    // Keep the function so that its body is still checked.
+    if let Err(errors) = errors.finish() {
+        let errors = errors.into_compile_error();
+        return Ok(quote!(#function #errors));
+    }
+    let FilterFunctionArgs { name, type_name } = args.unwrap();
+
+    let vis = &function.vis;
+    let fn_name = &function.sig.ident;
+    let lifetime = lifetimes
+        .first()
+        .map(|&l| l.clone())
+        .unwrap_or_else(|| syn::Lifetime::new("'core", Span::call_site()));
+
+    let push_parameters = parameters.iter().filter_map(|parameter| match parameter {
+        Parameter::Argument { name, ty } => Some(quote_spanned! {ty.span()=>
+            ::vapoursynth::plugins::push_parameter::<#ty>(&mut args, #name, false);
+        }),
+        _ => None,
+    });
+
+    let call_args = parameters.iter().map(|parameter| match parameter {
+        Parameter::Api => quote!(api),
+        Parameter::Core => quote!(core),
+        Parameter::Argument { name, ty } => quote_spanned! {ty.span()=>
+            <#ty as ::vapoursynth::plugins::FilterParameter>::get_from_map(args, #name)?
+        },
+    });
+
+    let call = quote_spanned! {function.sig.output.span()=>
+        #fn_name(#(#call_args),*)
+    };
+
+    Ok(quote! {
+        #function
+
+        #vis struct #type_name {
+            args: ::std::string::String,
+        }
+
+        impl #type_name {
+            #[allow(clippy::new_without_default)]
+            #vis fn new() -> Self {
+                fn make_args<#lifetime>() -> ::std::string::String {
+                    let mut args = ::std::string::String::new();
+                    #(#push_parameters)*
+                    args
+                }
+
+                Self { args: make_args() }
+            }
+        }
+
+        impl ::vapoursynth::plugins::FilterFunction for #type_name {
+            #[inline]
+            fn name(&self) -> &str {
+                #name
+            }
+
+            #[inline]
+            fn args(&self) -> &str {
+                &self.args
+            }
+
+            #[inline]
+            fn create<#lifetime>(
+                &self,
+                api: ::vapoursynth::api::API,
+                core: ::vapoursynth::core::CoreRef<#lifetime>,
+                args: &::vapoursynth::map::Map<#lifetime>,
+            ) -> ::vapoursynth::anyhow::Result<
+                ::std::option::Option<
+                    ::std::boxed::Box<dyn ::vapoursynth::plugins::Filter<#lifetime> + #lifetime>,
+                >,
+            > {
+                #call
+            }
+        }
+    })
+}
+
+/// Arguments of the `plugin` attribute.
+struct PluginArgs {
+    identifier: LitStr,
+    namespace: LitStr,
+    name: LitStr,
+    read_only: LitBool,
+    functions: Vec<Expr>,
+    version: Option<Expr>,
+    required_api_version: Option<Expr>,
+}
+
+impl PluginArgs {
+    /// Parses the attribute arguments.
+    fn parse(args: TokenStream) -> syn::Result<Self> {
+        let mut identifier = None;
+        let mut namespace = None;
+        let mut name = None;
+        let mut read_only = None;
+        let mut functions = None;
+        let mut version = None;
+        let mut required_api_version = None;
+
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("identifier") {
+                set_once(&meta, &mut identifier, |meta| {
+                    meta.value()?.parse::<LitStr>()
+                })
+            } else if meta.path.is_ident("namespace") {
+                set_once(&meta, &mut namespace, |meta| {
+                    meta.value()?.parse::<LitStr>()
+                })
+            } else if meta.path.is_ident("name") {
+                set_once(&meta, &mut name, |meta| meta.value()?.parse::<LitStr>())
+            } else if meta.path.is_ident("read_only") {
+                set_once(&meta, &mut read_only, |meta| {
+                    meta.value()?.parse::<LitBool>()
+                })
+            } else if meta.path.is_ident("functions") {
+                set_once(&meta, &mut functions, |meta| {
+                    let input = meta.value()?;
+                    let content;
+                    syn::bracketed!(content in input);
+                    let list = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+                    Ok(list.into_iter().collect())
+                })
+            } else if meta.path.is_ident("version") {
+                set_once(&meta, &mut version, |meta| meta.value()?.parse::<Expr>())
+            } else if meta.path.is_ident("required_api_version") {
+                set_once(&meta, &mut required_api_version, |meta| {
+                    meta.value()?.parse::<Expr>()
+                })
+            } else {
+                Err(meta.error(
+                    "unsupported argument, expected `identifier`, `namespace`, `name`, \
+                     `read_only`, `functions`, `version` or `required_api_version`",
+                ))
+            }
+        });
+        parser.parse2(args)?;
+
+        let missing = |what: &str| {
+            syn::Error::new(
+                Span::call_site(),
+                format!("missing plugin {0}, add `{0} = \"...\"`", what),
+            )
+        };
+
+        let identifier = identifier.ok_or_else(|| missing("identifier"))?;
+        if identifier.value().is_empty() || identifier.value().contains(char::is_whitespace) {
+            return Err(syn::Error::new(
+                identifier.span(),
+                "the plugin identifier must be non-empty and can't contain whitespace",
+            ));
+        }
+
+        let namespace = namespace.ok_or_else(|| missing("namespace"))?;
+        let value = namespace.value();
+        if value.is_empty()
+            || !value.starts_with(|c: char| c.is_ascii_lowercase())
+            || !value
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(syn::Error::new(
+                namespace.span(),
+                format!(
+                    "`{}` is not a valid namespace: it must start with a lowercase letter and \
+                     contain only lowercase letters, digits and underscores",
+                    value
+                ),
+            ));
+        }
+
+        let name = name.ok_or_else(|| missing("name"))?;
+        if name.value().contains('\0') {
+            return Err(syn::Error::new(
+                name.span(),
+                "the plugin name can't contain NUL characters",
+            ));
+        }
+
+        Ok(Self {
+            identifier,
+            namespace,
+            name,
+            read_only: read_only.unwrap_or_else(|| LitBool::new(false, Span::call_site())),
+            functions: functions.unwrap_or_default(),
+            version,
+            required_api_version,
+        })
+    }
+}
+
+pub(crate) fn plugin(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let args = PluginArgs::parse(args);
+    let mut module: ItemMod = syn::parse2(item)?;
+
+    let mut errors = Errors::default();
+    let args = args.map_err(|e| errors.push(e)).ok();
+
+    let mut type_names = Vec::new();
+    match &mut module.content {
+        None => errors.push(syn::Error::new_spanned(
+            &module,
+            "the plugin module must be inline: `mod name { ... }`",
+        )),
+        Some((_, items)) => {
+            for item in items.iter() {
+                let Item::Fn(function) = item else { continue };
+                for attr in function
+                    .attrs
+                    .iter()
+                    .filter(|a| is_filter_function_attribute(a))
+                {
+                    match FilterFunctionArgs::from_attribute(attr) {
+                        Ok(args) => type_names.push(args.type_name),
+                        Err(e) => errors.push(e),
+                    }
+                }
+            }
+        }
+    }
+
+    // Keep the module so that the errors inside it are still reported.
+    if let Err(errors) = errors.finish() {
+        let errors = errors.into_compile_error();
+        return Ok(quote!(#module #errors));
+    }
+    let PluginArgs {
+        identifier,
+        namespace,
+        name,
+        read_only,
+        functions,
+        version,
+        required_api_version,
+    } = args.unwrap();
+
+    let version = version.map(|version| quote!(, version = #version));
+    let required_api_version = required_api_version
+        .map(|required_api_version| quote!(, required_api_version = #required_api_version));
+
+    let export = quote! {
+        ::vapoursynth::export_vapoursynth_plugin! {
+            ::vapoursynth::plugins::Metadata {
+                identifier: #identifier,
+                namespace: #namespace,
+                name: #name,
+                read_only: #read_only,
+            },
+            [#(#type_names::new(),)* #(#functions,)*]
+            #version
+            #required_api_version
+        }
+    };
+
+    let (_, items) = module.content.as_mut().unwrap();
+    items.push(Item::Verbatim(export));
+
+    Ok(quote!(#module))
+}
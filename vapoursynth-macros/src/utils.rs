@@ -0,0 +1,85 @@
+use syn::LitStr;
+
+/// Checks that the string is a valid map key.
+///
+/// Mirrors `vapoursynth::map::Map::is_key_valid()`.
+pub(crate) fn validate_key(key: &LitStr) -> syn::Result<()> {
+    let value = key.value();
+    let mut chars = value.chars();
+
+    let valid = match chars.next() {
+        None => return Err(syn::Error::new(key.span(), "the key is empty")),
+        Some(first) => {
+            (first.is_ascii_alphabetic() || first == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+    };
+
+    if !valid {
+        return Err(syn::Error::new(
+            key.span(),
+            format!(
+                "`{}` is not a valid key: keys must start with a letter or an underscore and \
+                 contain only alphanumeric characters and underscores",
+                value
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// An accumulator for reporting several errors at once.
+#[derive(Default)]
+pub(crate) struct Errors(Option<syn::Error>);
+
+impl Errors {
+    /// Adds an error.
+    pub(crate) fn push(&mut self, error: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+
+    /// Returns all added errors combined, if any.
+    pub(crate) fn finish(self) -> syn::Result<()> {
+        match self.0 {
+            Some(errors) => Err(errors),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Checks that the string is a valid function or argument name: `^[a-zA-Z][a-zA-Z0-9_]*$`.
+pub(crate) fn validate_name(name: &LitStr, what: &str) -> syn::Result<()> {
+    let value = name.value();
+    let mut chars = value.chars();
+
+    let valid = match chars.next() {
+        None => false,
+        Some(first) => {
+            first.is_ascii_alphabetic() && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+    };
+
+    if !valid {
+        return Err(syn::Error::new(
+            name.span(),
+            format!(
+                "`{}` is not a valid {}: it must start with a letter and contain only \
+                 alphanumeric characters and underscores",
+                value, what
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the name of the identifier without the `r#` prefix as a string literal.
+pub(crate) fn ident_to_lit(ident: &syn::Ident) -> LitStr {
+    let name = ident.to_string();
+    let name = name.strip_prefix("r#").unwrap_or(&name);
+    LitStr::new(name, ident.span())
+}
@@ -1,2 +1,3 @@
+#define VSSCRIPT_USE_API_42
 #include "VSScript4.h"
 #include "VapourSynth4.h"
\ No newline at end of file
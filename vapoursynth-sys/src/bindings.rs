@@ -104,7 +104,7 @@ pub const VAPOURSYNTH_API_MAJOR: u32 = 4;
 pub const VAPOURSYNTH_API_MINOR: u32 = 0;
 pub const VS_AUDIO_FRAME_SAMPLES: u32 = 3072;
 pub const VSSCRIPT_API_MAJOR: u32 = 4;
-pub const VSSCRIPT_API_MINOR: u32 = 1;
+pub const VSSCRIPT_API_MINOR: u32 = 2;
 pub type __u_char = ::std::os::raw::c_uchar;
 pub type __u_short = ::std::os::raw::c_ushort;
 pub type __u_int = ::std::os::raw::c_uint;
@@ -1479,10 +1479,17 @@ pub struct VSSCRIPTAPI {
     pub evalSetWorkingDir: ::std::option::Option<
         unsafe extern "C" fn(handle: *mut VSScript, setCWD: ::std::os::raw::c_int),
     >,
+    pub getAvailableOutputNodes: ::std::option::Option<
+        unsafe extern "C" fn(
+            handle: *mut VSScript,
+            size: ::std::os::raw::c_int,
+            dst: *mut ::std::os::raw::c_int,
+        ) -> ::std::os::raw::c_int,
+    >,
 }
 #[allow(clippy::unnecessary_operation, clippy::identity_op)]
 const _: () = {
-    ["Size of VSSCRIPTAPI"][::std::mem::size_of::<VSSCRIPTAPI>() - 120usize];
+    ["Size of VSSCRIPTAPI"][::std::mem::size_of::<VSSCRIPTAPI>() - 128usize];
     ["Alignment of VSSCRIPTAPI"][::std::mem::align_of::<VSSCRIPTAPI>() - 8usize];
     ["Offset of field: VSSCRIPTAPI::getAPIVersion"]
         [::std::mem::offset_of!(VSSCRIPTAPI, getAPIVersion) - 0usize];
@@ -1514,4 +1521,6 @@ const _: () = {
         [::std::mem::offset_of!(VSSCRIPTAPI, freeScript) - 104usize];
     ["Offset of field: VSSCRIPTAPI::evalSetWorkingDir"]
         [::std::mem::offset_of!(VSSCRIPTAPI, evalSetWorkingDir) - 112usize];
+    ["Offset of field: VSSCRIPTAPI::getAvailableOutputNodes"]
+        [::std::mem::offset_of!(VSSCRIPTAPI, getAvailableOutputNodes) - 120usize];
 };
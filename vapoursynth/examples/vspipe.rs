@@ -8,21 +8,21 @@ mod inner {
     extern crate num_rational;
     extern crate vapoursynth;
 
-    use std::cmp;
-    use std::collections::HashMap;
+    use std::cell::Cell;
     use std::ffi::OsStr;
     use std::fmt::Debug;
     use std::fs::File;
     use std::io::{self, Stdout, Write, stdout};
     use std::ops::Deref;
-    use std::sync::{Arc, Condvar, Mutex};
     use std::time::Instant;
 
     use anyhow::{Context, Error, anyhow, bail, ensure};
 
     use self::clap::{Arg, Command};
     use self::num_rational::Ratio;
+    use self::vapoursynth::core::CoreRef;
     use self::vapoursynth::prelude::*;
+    use self::vapoursynth::render::Render;
     use super::*;
 
     enum OutputTarget {
@@ -41,25 +41,11 @@ mod inner {
         progress: bool,
     }
 
-    struct OutputState<'core> {
+    struct OutputState {
         output_target: OutputTarget,
         timecodes_file: Option<File>,
-        error: Option<(usize, Error)>,
-        reorder_map: HashMap<usize, (Option<FrameRef<'core>>, Option<FrameRef<'core>>)>,
-        last_requested_frame: usize,
-        next_output_frame: usize,
         current_timecode: Ratio<i64>,
-        callbacks_fired: usize,
-        callbacks_fired_alpha: usize,
-        last_fps_report_time: Instant,
-        last_fps_report_frames: usize,
-        fps: Option<f64>,
-    }
-
-    struct SharedData<'core> {
-        output_done_pair: (Mutex<bool>, Condvar),
-        output_parameters: OutputParameters<'core>,
-        output_state: Mutex<OutputState<'core>>,
+        frames_output: usize,
     }
 
     impl Write for OutputTarget {
@@ -222,11 +208,6 @@ mod inner {
         Ok(())
     }
 
-    // Checks if the frame is completed, that is, we have the frame and, if needed, its alpha part.
-    fn is_completed(entry: &(Option<FrameRef>, Option<FrameRef>), have_alpha: bool) -> bool {
-        entry.0.is_some() && (!have_alpha || entry.1.is_some())
-    }
-
     fn print_frame<W: Write>(writer: &mut W, frame: &Frame) -> Result<(), Error> {
         const RGB_REMAP: [usize; 3] = [1, 2, 0];
 
@@ -270,176 +251,83 @@ mod inner {
     }
 
     fn update_timecodes(frame: &Frame, state: &mut OutputState) -> Result<(), Error> {
-        let props = frame.props();
-        let duration_num = props
-            .get_int("_DurationNum")
-            .context("Couldn't get the duration numerator")?;
-        let duration_den = props
-            .get_int("_DurationDen")
-            .context("Couldn't get the duration denominator")?;
-
-        if duration_den == 0 {
-            bail!("The duration denominator is zero");
-        }
+        let duration = frame
+            .duration()
+            .context("Couldn't get the frame duration")?
+            .ok_or_else(|| anyhow!("The frame duration isn't set"))?;
 
-        state.current_timecode += Ratio::new(duration_num, duration_den);
+        state.current_timecode += Ratio::new(duration.numerator, duration.denominator);
 
         Ok(())
     }
 
-    fn frame_done_callback<'core>(
-        frame: Result<FrameRef<'core>, GetFrameError>,
-        n: usize,
-        _node: &Node<'core>,
-        shared_data: &Arc<SharedData<'core>>,
-        alpha: bool,
-    ) {
-        let parameters = &shared_data.output_parameters;
-        let mut state = shared_data.output_state.lock().unwrap();
-
-        // Increase the progress counter.
-        if !alpha {
-            state.callbacks_fired += 1;
-            if parameters.alpha_node.is_none() {
-                state.callbacks_fired_alpha += 1;
-            }
-        } else {
-            state.callbacks_fired_alpha += 1;
-        }
-
-        // Figure out the FPS.
-        if parameters.progress {
-            let current = Instant::now();
-            let elapsed = current.duration_since(state.last_fps_report_time);
-            let elapsed_seconds = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9;
-
-            if elapsed.as_secs() > 10 {
-                state.fps = Some(
-                    (state.callbacks_fired - state.last_fps_report_frames) as f64 / elapsed_seconds,
-                );
-                state.last_fps_report_time = current;
-                state.last_fps_report_frames = state.callbacks_fired;
-            }
-        }
-
-        match frame {
-            Err(error) => {
-                if state.error.is_none() {
-                    state.error = Some((
-                        n,
-                        anyhow!(error.into_inner().to_string_lossy().into_owned()),
-                    ))
-                }
-            }
-            Ok(frame) => {
-                // Store the frame in the reorder map.
-                {
-                    let entry = state.reorder_map.entry(n).or_insert((None, None));
-                    if alpha {
-                        entry.1 = Some(frame);
-                    } else {
-                        entry.0 = Some(frame);
-                    }
-                }
-
-                // If we got both a frame and its alpha frame, request one more.
-                if is_completed(&state.reorder_map[&n], parameters.alpha_node.is_some())
-                    && state.last_requested_frame < parameters.end_frame
-                    && state.error.is_none()
-                {
-                    let shared_data_2 = shared_data.clone();
-                    parameters.node.get_frame_async(
-                        state.last_requested_frame + 1,
-                        move |frame, n, node| {
-                            frame_done_callback(frame, n, &node, &shared_data_2, false)
-                        },
+    fn output_frames(
+        core: CoreRef,
+        parameters: &OutputParameters,
+        state: &mut OutputState,
+    ) -> Result<(), Error> {
+        let range = parameters.start_frame..parameters.end_frame + 1;
+
+        // The frames are output in order, so the alpha frames can be matched with the frames
+        // simply by iterating them at the same pace. Their request window follows the one of the
+        // render, which shrinks when the core is low on memory.
+        let mut alpha_frames = parameters
+            .alpha_node
+            .as_ref()
+            .map(|alpha_node| alpha_node.frames(range.clone(), parameters.requests));
+        let requests = Cell::new(parameters.requests);
+
+        let render = Render::new(core, &parameters.node, range)
+            .requests(parameters.requests)
+            .on_progress(|progress| {
+                requests.set(progress.requests);
+
+                if parameters.progress {
+                    eprint!(
+                        "Frame: {}/{} ({:.2} fps)\r",
+                        progress.frames_done, progress.total_frames, progress.fps
                     );
-
-                    if let Some(ref alpha_node) = parameters.alpha_node {
-                        let shared_data_2 = shared_data.clone();
-                        alpha_node.get_frame_async(
-                            state.last_requested_frame + 1,
-                            move |frame, n, node| {
-                                frame_done_callback(frame, n, &node, &shared_data_2, true)
-                            },
-                        );
-                    }
-
-                    state.last_requested_frame += 1;
                 }
-
-                // Output all completed frames.
-                while state
-                    .reorder_map
-                    .get(&state.next_output_frame)
-                    .map(|entry| is_completed(entry, parameters.alpha_node.is_some()))
-                    .unwrap_or(false)
-                {
-                    let next_output_frame = state.next_output_frame;
-                    let (frame, alpha_frame) =
-                        state.reorder_map.remove(&next_output_frame).unwrap();
-
-                    let frame = frame.unwrap();
-                    if state.error.is_none()
-                        && let Err(error) = print_frames(
-                            &mut state.output_target,
-                            parameters,
-                            &frame,
-                            alpha_frame.as_deref(),
-                        )
-                    {
-                        state.error = Some((n, error));
-                    }
-
-                    if state.timecodes_file.is_some() && state.error.is_none() {
-                        let timecode = (*state.current_timecode.numer() as f64 * 1000f64)
-                            / *state.current_timecode.denom() as f64;
-                        match writeln!(state.timecodes_file.as_mut().unwrap(), "{:.6}", timecode)
-                            .context("Couldn't output the timecode")
-                        {
-                            Err(error) => state.error = Some((n, error)),
-                            Ok(()) => {
-                                if let Err(error) = update_timecodes(&frame, &mut state)
-                                    .context("Couldn't update the timecodes")
-                                {
-                                    state.error = Some((n, error));
-                                }
-                            }
-                        }
-                    }
-
-                    state.next_output_frame += 1;
+            });
+
+        render.run(|n, frame| {
+            let alpha_frame = match alpha_frames {
+                Some(ref mut alpha_frames) => {
+                    alpha_frames.set_max_in_flight(requests.get());
+                    Some(
+                        alpha_frames
+                            .next()
+                            .unwrap()
+                            .with_context(|| format!("Failed to retrieve alpha frame {}", n))?,
+                    )
                 }
-            }
-        }
+                None => None,
+            };
 
-        // Output the progress info.
-        if parameters.progress {
-            eprint!(
-                "Frame: {}/{}",
-                state.callbacks_fired,
-                parameters.end_frame - parameters.start_frame + 1
-            );
+            print_frames(
+                &mut state.output_target,
+                parameters,
+                &frame,
+                alpha_frame.as_deref(),
+            )?;
 
-            if let Some(fps) = state.fps {
-                eprint!(" ({:.2} fps)", fps);
+            if let Some(ref mut timecodes_file) = state.timecodes_file {
+                let timecode = (*state.current_timecode.numer() as f64 * 1000f64)
+                    / *state.current_timecode.denom() as f64;
+                writeln!(timecodes_file, "{:.6}", timecode)
+                    .context("Couldn't output the timecode")?;
+                update_timecodes(&frame, state).context("Couldn't update the timecodes")?;
             }
 
-            eprint!("\r");
-        }
+            state.frames_output += 1;
+            Ok(())
+        })?;
 
-        // if state.next_output_frame == parameters.end_frame + 1 {
-        // This condition works with error handling:
-        let frames_requested = state.last_requested_frame - parameters.start_frame + 1;
-        if state.callbacks_fired == frames_requested
-            && state.callbacks_fired_alpha == frames_requested
-        {
-            *shared_data.output_done_pair.0.lock().unwrap() = true;
-            shared_data.output_done_pair.1.notify_one();
-        }
+        Ok(())
     }
 
     fn output(
+        core: CoreRef,
         mut output_target: OutputTarget,
         mut timecodes_file: Option<File>,
         parameters: OutputParameters,
@@ -459,73 +347,29 @@ mod inner {
             writeln!(timecodes_file, "# timecode format v2")?;
         }
 
-        let initial_requests = cmp::min(
-            parameters.requests,
-            parameters.end_frame - parameters.start_frame + 1,
-        );
+        // Record the start time.
+        let start_time = Instant::now();
 
-        let output_done_pair = (Mutex::new(false), Condvar::new());
-        let output_state = Mutex::new(OutputState {
+        let mut state = OutputState {
             output_target,
             timecodes_file,
-            error: None,
-            reorder_map: HashMap::new(),
-            last_requested_frame: parameters.start_frame + initial_requests - 1,
-            next_output_frame: 0,
             current_timecode: Ratio::from_integer(0),
-            callbacks_fired: 0,
-            callbacks_fired_alpha: 0,
-            last_fps_report_time: Instant::now(),
-            last_fps_report_frames: 0,
-            fps: None,
-        });
-        let shared_data = Arc::new(SharedData {
-            output_done_pair,
-            output_parameters: parameters,
-            output_state,
-        });
-
-        // Record the start time.
-        let start_time = Instant::now();
-
-        // Start off by requesting some frames.
-        {
-            let parameters = &shared_data.output_parameters;
-            for n in 0..initial_requests {
-                let shared_data_2 = shared_data.clone();
-                parameters.node.get_frame_async(n, move |frame, n, node| {
-                    frame_done_callback(frame, n, &node, &shared_data_2, false)
-                });
-
-                if let Some(ref alpha_node) = parameters.alpha_node {
-                    let shared_data_2 = shared_data.clone();
-                    alpha_node.get_frame_async(n, move |frame, n, node| {
-                        frame_done_callback(frame, n, &node, &shared_data_2, true)
-                    });
-                }
-            }
-        }
+            frames_output: 0,
+        };
 
-        let (lock, cvar) = &shared_data.output_done_pair;
-        let mut done = lock.lock().unwrap();
-        while !*done {
-            done = cvar.wait(done).unwrap();
-        }
+        let result = output_frames(core, &parameters, &mut state);
 
         let elapsed = start_time.elapsed();
         let elapsed_seconds = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9;
 
-        let mut state = shared_data.output_state.lock().unwrap();
         eprintln!(
             "Output {} frames in {:.2} seconds ({:.2} fps)",
-            state.next_output_frame,
+            state.frames_output,
             elapsed_seconds,
-            state.next_output_frame as f64 / elapsed_seconds
+            state.frames_output as f64 / elapsed_seconds
         );
 
-        if let Some((n, ref msg)) = state.error {
-            bail!("Failed to retrieve frame {} with error: {}", n, msg);
-        }
+        result?;
 
         // Flush the output file.
         state
@@ -694,7 +538,7 @@ mod inner {
             for arg in args.map(parse_arg) {
                 let (name, value) = arg.context("Couldn't parse an argument")?;
                 args_map
-                    .append_data(name, value.as_bytes())
+                    .append_str(name, value)
                     .context("Couldn't append an argument value")?;
             }
 
@@ -781,6 +625,8 @@ mod inner {
                 );
             }
 
+            let core = environment.get_core().context("Couldn't get the core")?;
+
             let requests = {
                 let requests = matches
                     .value_of("requests")
@@ -789,7 +635,7 @@ mod inner {
                     .context("Couldn't convert the request count to an unsigned integer")?;
 
                 if requests == 0 {
-                    environment.get_core().unwrap().info().num_threads
+                    core.info().num_threads
                 } else {
                     requests
                 }
@@ -799,6 +645,7 @@ mod inner {
             let progress = matches.is_present("progress");
 
             output(
+                core,
                 output_target,
                 timecodes_file,
                 OutputParameters {
@@ -25,6 +25,7 @@ static RAW_API: AtomicPtr<ffi::VSAPI> = AtomicPtr::new(ptr::null_mut());
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum MessageType {
     Debug,
+    Information,
     Warning,
     Critical,
 
@@ -140,6 +141,24 @@ impl API {
         Ok(())
     }
 
+    /// Adds a log handler to `core`.
+    ///
+    /// `free` is called with `user_data` when the handler is removed or the core is freed.
+    ///
+    /// # Safety
+    /// The caller must ensure `core` is valid and `handler` and `free` can be called with
+    /// `user_data`.
+    #[inline]
+    pub(crate) unsafe fn add_log_handler(
+        self,
+        handler: ffi::VSLogHandler,
+        free: ffi::VSLogHandlerFree,
+        user_data: *mut c_void,
+        core: *mut ffi::VSCore,
+    ) -> *mut ffi::VSLogHandle {
+        (self.handle.as_ref().addLogHandler.unwrap())(handler, free, user_data, core)
+    }
+
     /// Frees `node`.
     ///
     /// # Safety
@@ -315,6 +334,15 @@ impl API {
         (self.handle.as_ref().clearMap.unwrap())(map);
     }
 
+    /// Copies all values from `src` into `dst`, replacing the values of the existing keys.
+    ///
+    /// # Safety
+    /// The caller must ensure `src` and `dst` are valid.
+    #[inline]
+    pub(crate) unsafe fn copy_map(self, src: &ffi::VSMap, dst: &mut ffi::VSMap) {
+        (self.handle.as_ref().copyMap.unwrap())(src, dst);
+    }
+
     /// Frees `map`.
     ///
     /// # Safety
@@ -388,6 +416,22 @@ impl API {
         (self.handle.as_ref().mapGetType.unwrap())(map, key)
     }
 
+    /// Creates an empty array of the given type under `key`.
+    ///
+    /// Returns non-zero if the key already exists.
+    ///
+    /// # Safety
+    /// The caller must ensure `map` and `key` are valid.
+    #[inline]
+    pub(crate) unsafe fn prop_set_empty(
+        self,
+        map: &mut ffi::VSMap,
+        key: *const c_char,
+        type_: ffi::VSPropertyType,
+    ) -> i32 {
+        (self.handle.as_ref().mapSetEmpty.unwrap())(map, key, type_ as c_int)
+    }
+
     /// Returns the size in bytes of a property of type ptData.
     ///
     /// # Safety
@@ -404,8 +448,11 @@ impl API {
     }
 
     prop_get_something!(prop_get_int, mapGetInt, i64);
+    prop_get_something!(prop_get_int_saturated, mapGetIntSaturated, c_int);
+    prop_get_something!(prop_get_float_saturated, mapGetFloatSaturated, f32);
     prop_get_something!(prop_get_float, mapGetFloat, f64);
     prop_get_something!(prop_get_data, mapGetData, *const c_char);
+    prop_get_something!(prop_get_data_type_hint, mapGetDataTypeHint, c_int);
     prop_get_something!(prop_get_node, mapGetNode, *mut ffi::VSNode);
     prop_get_something!(prop_get_frame, mapGetFrame, *const ffi::VSFrame);
     prop_get_something!(prop_get_func, mapGetFunction, *mut ffi::VSFunction);
@@ -415,6 +462,9 @@ impl API {
     prop_set_something!(prop_set_node, mapSetNode, *mut ffi::VSNode);
     prop_set_something!(prop_set_frame, mapSetFrame, *const ffi::VSFrame);
     prop_set_something!(prop_set_func, mapSetFunction, *mut ffi::VSFunction);
+    prop_set_something!(prop_consume_node, mapConsumeNode, *mut ffi::VSNode);
+    prop_set_something!(prop_consume_frame, mapConsumeFrame, *const ffi::VSFrame);
+    prop_set_something!(prop_consume_func, mapConsumeFunction, *mut ffi::VSFunction);
 
     /// Retrieves an array of integers from a map.
     ///
@@ -457,6 +507,7 @@ impl API {
         map: &mut ffi::VSMap,
         key: *const c_char,
         value: &[u8],
+        type_hint: ffi::VSDataTypeHint,
         append: ffi::VSMapAppendMode,
     ) -> i32 {
         let length = value.len();
@@ -468,7 +519,7 @@ impl API {
             key,
             value.as_ptr() as _,
             length,
-            ffi::VSDataTypeHint_dtUnknown, // type hint
+            type_hint,
             append as i32,
         )
     }
@@ -803,6 +854,15 @@ impl API {
         (self.handle.as_ref().getPluginPath.unwrap())(plugin)
     }
 
+    /// Returns the version of the plugin.
+    ///
+    /// # Safety
+    /// The caller must ensure all pointers are valid.
+    #[inline]
+    pub(crate) unsafe fn get_plugin_version(self, plugin: *mut ffi::VSPlugin) -> c_int {
+        (self.handle.as_ref().getPluginVersion.unwrap())(plugin)
+    }
+
     /// Invokes a filter.
     ///
     /// # Safety
@@ -910,6 +970,26 @@ impl API {
         }
     }
 
+    /// Creates a new core with the given `VSCoreCreationFlags` and returns a pointer to it.
+    ///
+    /// # Safety
+    /// The caller is responsible for the returned core: it must either be freed with `freeCore()`
+    /// or have its ownership transferred, for example to `VSSCRIPTAPI::createScript()`.
+    #[inline]
+    pub(crate) unsafe fn create_core_with_flags(self, flags: c_int) -> *mut ffi::VSCore {
+        (self.handle.as_ref().createCore.unwrap())(flags)
+    }
+
+    /// Frees `core`.
+    ///
+    /// # Safety
+    /// The caller must ensure `core` is valid, all frame requests have completed and all objects
+    /// belonging to the core have been released.
+    #[inline]
+    pub(crate) unsafe fn free_core(self, core: *mut ffi::VSCore) {
+        (self.handle.as_ref().freeCore.unwrap())(core);
+    }
+
     /// Returns a pointer to a plugin function with the given name, or a null pointer if not found.
     ///
     /// # Safety
@@ -962,9 +1042,10 @@ impl API {
 
 impl MessageType {
     #[inline]
-    fn ffi_type(self) -> c_int {
+    pub(crate) fn ffi_type(self) -> c_int {
         let rv = match self {
             MessageType::Debug => ffi::VSMessageType_mtDebug,
+            MessageType::Information => ffi::VSMessageType_mtInformation,
             MessageType::Warning => ffi::VSMessageType_mtWarning,
             MessageType::Critical => ffi::VSMessageType_mtCritical,
             MessageType::Fatal => ffi::VSMessageType_mtFatal,
@@ -973,10 +1054,10 @@ impl MessageType {
     }
 
     #[inline]
-    #[expect(dead_code)]
-    fn from_ffi_type(x: c_int) -> Option<Self> {
+    pub(crate) fn from_ffi_type(x: c_int) -> Option<Self> {
         match x {
             x if x == ffi::VSMessageType_mtDebug as c_int => Some(MessageType::Debug),
+            x if x == ffi::VSMessageType_mtInformation as c_int => Some(MessageType::Information),
             x if x == ffi::VSMessageType_mtWarning as c_int => Some(MessageType::Warning),
             x if x == ffi::VSMessageType_mtCritical as c_int => Some(MessageType::Critical),
             x if x == ffi::VSMessageType_mtFatal as c_int => Some(MessageType::Fatal),
@@ -3,11 +3,16 @@
 use std::ffi::{CStr, CString, NulError};
 use std::fmt;
 use std::marker::PhantomData;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr::NonNull;
+use std::sync::{Arc, Condvar, Mutex};
 use vapoursynth_sys as ffi;
 
 use crate::api::API;
 use crate::format::{ColorFamily, Format, FormatID, SampleType};
+use crate::frame::FrameRef;
+use crate::node::{GetFrameError, Node};
 use crate::plugin::Plugin;
 
 /// Contains information about a VapourSynth core.
@@ -167,6 +172,147 @@ impl<'core> CoreRef<'core> {
     pub fn set_thread_count(&self, threads: i32) -> i32 {
         unsafe { API::get_cached().set_thread_count(threads, self.handle.as_ptr()) }
     }
+
+    /// Creates a scope for asynchronous frame requests.
+    ///
+    /// Frames requested with `Scope::get_frame_async()` are guaranteed to be delivered before
+    /// this function returns: it waits for all outstanding callbacks, including ones requested
+    /// from within other callbacks. This makes it impossible to drop the core with requests in
+    /// flight and lets the callbacks borrow local variables, similarly to `std::thread::scope()`.
+    ///
+    /// If `f` panics, the outstanding callbacks are still waited for before the panic is resumed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use vapoursynth::prelude::*;
+    /// # use vapoursynth::core::CoreRef;
+    /// # use std::sync::Mutex;
+    /// # fn foo(core: CoreRef, node: &Node) {
+    /// let widths = Mutex::new(Vec::new());
+    ///
+    /// core.scope(|s| {
+    ///     for n in 0..10 {
+    ///         s.get_frame_async(node, n, |frame, _n, _node| {
+    ///             widths.lock().unwrap().push(frame.unwrap().width(0));
+    ///         });
+    ///     }
+    /// });
+    ///
+    /// // All callbacks have finished by now.
+    /// assert_eq!(widths.into_inner().unwrap().len(), 10);
+    /// # }
+    /// ```
+    pub fn scope<'env, F, T>(self, f: F) -> T
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env, 'core>) -> T,
+    {
+        let scope = Scope {
+            core: self,
+            pending: Arc::new(Pending::new()),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+        scope.pending.wait();
+
+        match result {
+            Ok(x) => x,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+/// A number of outstanding asynchronous requests which can be waited for.
+#[derive(Debug)]
+pub(crate) struct Pending {
+    count: Mutex<usize>,
+    condvar: Condvar,
+}
+
+impl Pending {
+    /// Creates a new counter with no outstanding requests.
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self {
+            count: Mutex::new(0),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Counts a new outstanding request, until the returned guard is dropped.
+    #[inline]
+    pub(crate) fn start(self: &Arc<Self>) -> PendingGuard {
+        *self.count.lock().unwrap() += 1;
+        PendingGuard(self.clone())
+    }
+
+    /// Waits until there are no outstanding requests.
+    pub(crate) fn wait(&self) {
+        let mut count = self.count.lock().unwrap();
+        while *count > 0 {
+            count = self.condvar.wait(count).unwrap();
+        }
+    }
+}
+
+/// Decrements the number of outstanding requests when dropped.
+pub(crate) struct PendingGuard(Arc<Pending>);
+
+impl Drop for PendingGuard {
+    #[inline]
+    fn drop(&mut self) {
+        let mut count = self.0.count.lock().unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.0.condvar.notify_all();
+        }
+    }
+}
+
+/// A scope for asynchronous frame requests, created by `CoreRef::scope()`.
+pub struct Scope<'scope, 'env: 'scope, 'core> {
+    core: CoreRef<'core>,
+    pending: Arc<Pending>,
+    // Invariance over the lifetimes, same as in `std::thread::Scope`.
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope, 'env, 'core> Scope<'scope, 'env, 'core> {
+    /// Returns the core this scope was created for.
+    #[inline]
+    pub fn core(&self) -> CoreRef<'core> {
+        self.core
+    }
+
+    /// Requests the generation of a frame. When the frame is ready, `callback` is called.
+    ///
+    /// This is the same as `Node::get_frame_async()`, except that the callback can borrow data
+    /// which outlives the scope, and the scope waits for the callback to finish.
+    ///
+    /// # Panics
+    /// Panics is `n` is greater than `i32::MAX`.
+    pub fn get_frame_async<F>(&'scope self, node: &Node<'core>, n: usize, callback: F)
+    where
+        F: FnOnce(Result<FrameRef<'core>, GetFrameError>, usize, Node<'core>) + Send + 'scope,
+    {
+        type Callback<'a, 'core> =
+            Box<dyn FnOnce(Result<FrameRef<'core>, GetFrameError>, usize, Node<'core>) + Send + 'a>;
+
+        let guard = self.pending.start();
+
+        let callback: Callback<'scope, 'core> = Box::new(move |frame, n, node| {
+            let _guard = guard;
+            callback(frame, n, node);
+        });
+
+        // The scope doesn't return until the callback has finished, so extending its lifetime is
+        // fine.
+        let callback: Callback<'core, 'core> = unsafe { mem::transmute(callback) };
+
+        node.get_frame_async(n, callback);
+    }
 }
 
 impl fmt::Display for Info {
@@ -1,6 +1,7 @@
 //! VapourSynth frames.
 
 use std::marker::PhantomData;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::ptr::{self, NonNull};
 use std::slice;
@@ -12,6 +13,10 @@ use crate::api::API;
 use crate::component::Component;
 use crate::core::CoreRef;
 use crate::format::Format;
+use crate::frame_props::{
+    self, ChromaLocation, ColorPrimaries, ColorRange, FieldBased, MatrixCoefficients, PictType,
+    Rational, TransferCharacteristics,
+};
 use crate::map::{MapRef, MapRefMut};
 use crate::video_info::Resolution;
 
@@ -125,6 +130,14 @@ impl<'core> FrameRef<'core> {
             frame: Frame::from_ptr(handle),
         }
     }
+
+    /// Returns the underlying pointer, transferring the reference to the caller.
+    #[inline]
+    pub(crate) fn into_ptr(self) -> *const ffi::VSFrame {
+        let ptr = self.frame.handle.as_ptr() as *const _;
+        mem::forget(self);
+        ptr
+    }
 }
 
 impl<'core> FrameRefMut<'core> {
@@ -500,4 +513,183 @@ impl<'core> Frame<'core> {
     pub fn props_mut(&mut self) -> MapRefMut<'_, '_> {
         unsafe { MapRefMut::from_ptr(API::get_cached().get_frame_props_rw(self)) }
     }
+
+    /// Returns the matrix coefficients (`_Matrix`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn matrix(&self) -> frame_props::Result<Option<MatrixCoefficients>> {
+        frame_props::get_int_property(&self.props())
+    }
+
+    /// Sets the matrix coefficients (`_Matrix`).
+    #[inline]
+    pub fn set_matrix(&mut self, x: MatrixCoefficients) -> frame_props::Result<()> {
+        frame_props::set_int_property(&mut self.props_mut(), x)
+    }
+
+    /// Returns the transfer characteristics (`_Transfer`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn transfer(&self) -> frame_props::Result<Option<TransferCharacteristics>> {
+        frame_props::get_int_property(&self.props())
+    }
+
+    /// Sets the transfer characteristics (`_Transfer`).
+    #[inline]
+    pub fn set_transfer(&mut self, x: TransferCharacteristics) -> frame_props::Result<()> {
+        frame_props::set_int_property(&mut self.props_mut(), x)
+    }
+
+    /// Returns the color primaries (`_Primaries`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn primaries(&self) -> frame_props::Result<Option<ColorPrimaries>> {
+        frame_props::get_int_property(&self.props())
+    }
+
+    /// Sets the color primaries (`_Primaries`).
+    #[inline]
+    pub fn set_primaries(&mut self, x: ColorPrimaries) -> frame_props::Result<()> {
+        frame_props::set_int_property(&mut self.props_mut(), x)
+    }
+
+    /// Returns the color range (`_ColorRange`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn color_range(&self) -> frame_props::Result<Option<ColorRange>> {
+        frame_props::get_int_property(&self.props())
+    }
+
+    /// Sets the color range (`_ColorRange`).
+    #[inline]
+    pub fn set_color_range(&mut self, x: ColorRange) -> frame_props::Result<()> {
+        frame_props::set_int_property(&mut self.props_mut(), x)
+    }
+
+    /// Returns the chroma sample position (`_ChromaLocation`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn chroma_location(&self) -> frame_props::Result<Option<ChromaLocation>> {
+        frame_props::get_int_property(&self.props())
+    }
+
+    /// Sets the chroma sample position (`_ChromaLocation`).
+    #[inline]
+    pub fn set_chroma_location(&mut self, x: ChromaLocation) -> frame_props::Result<()> {
+        frame_props::set_int_property(&mut self.props_mut(), x)
+    }
+
+    /// Returns the field order (`_FieldBased`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn field_based(&self) -> frame_props::Result<Option<FieldBased>> {
+        frame_props::get_int_property(&self.props())
+    }
+
+    /// Sets the field order (`_FieldBased`).
+    #[inline]
+    pub fn set_field_based(&mut self, x: FieldBased) -> frame_props::Result<()> {
+        frame_props::set_int_property(&mut self.props_mut(), x)
+    }
+
+    /// Returns the sample aspect ratio (`_SARNum` and `_SARDen`).
+    ///
+    /// Returns `Ok(None)` if either of the properties isn't set.
+    #[inline]
+    pub fn sample_aspect_ratio(&self) -> frame_props::Result<Option<Rational>> {
+        frame_props::get_rational(&self.props(), (frame_props::SAR_NUM, frame_props::SAR_DEN))
+    }
+
+    /// Sets the sample aspect ratio (`_SARNum` and `_SARDen`).
+    #[inline]
+    pub fn set_sample_aspect_ratio(&mut self, x: Rational) -> frame_props::Result<()> {
+        frame_props::set_rational(
+            &mut self.props_mut(),
+            (frame_props::SAR_NUM, frame_props::SAR_DEN),
+            x,
+        )
+    }
+
+    /// Returns the frame duration in seconds (`_DurationNum` and `_DurationDen`).
+    ///
+    /// Returns `Ok(None)` if either of the properties isn't set.
+    #[inline]
+    pub fn duration(&self) -> frame_props::Result<Option<Rational>> {
+        frame_props::get_rational(
+            &self.props(),
+            (frame_props::DURATION_NUM, frame_props::DURATION_DEN),
+        )
+    }
+
+    /// Sets the frame duration in seconds (`_DurationNum` and `_DurationDen`).
+    #[inline]
+    pub fn set_duration(&mut self, x: Rational) -> frame_props::Result<()> {
+        frame_props::set_rational(
+            &mut self.props_mut(),
+            (frame_props::DURATION_NUM, frame_props::DURATION_DEN),
+            x,
+        )
+    }
+
+    /// Returns the picture type (`_PictType`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn pict_type(&self) -> frame_props::Result<Option<PictType>> {
+        frame_props::get_pict_type(&self.props())
+    }
+
+    /// Sets the picture type (`_PictType`).
+    #[inline]
+    pub fn set_pict_type(&mut self, x: PictType) -> frame_props::Result<()> {
+        frame_props::set_pict_type(&mut self.props_mut(), x)
+    }
+
+    /// Returns whether the frame needs deinterlacing (`_Combed`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn combed(&self) -> frame_props::Result<Option<bool>> {
+        frame_props::get_bool(&self.props(), frame_props::COMBED)
+    }
+
+    /// Sets whether the frame needs deinterlacing (`_Combed`).
+    #[inline]
+    pub fn set_combed(&mut self, x: bool) -> frame_props::Result<()> {
+        frame_props::set_bool(&mut self.props_mut(), frame_props::COMBED, x)
+    }
+
+    /// Returns whether the frame is the last one of a scene (`_SceneChangeNext`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn scene_change_next(&self) -> frame_props::Result<Option<bool>> {
+        frame_props::get_bool(&self.props(), frame_props::SCENE_CHANGE_NEXT)
+    }
+
+    /// Sets whether the frame is the last one of a scene (`_SceneChangeNext`).
+    #[inline]
+    pub fn set_scene_change_next(&mut self, x: bool) -> frame_props::Result<()> {
+        frame_props::set_bool(&mut self.props_mut(), frame_props::SCENE_CHANGE_NEXT, x)
+    }
+
+    /// Returns whether the frame is the first one of a scene (`_SceneChangePrev`).
+    ///
+    /// Returns `Ok(None)` if the property isn't set.
+    #[inline]
+    pub fn scene_change_prev(&self) -> frame_props::Result<Option<bool>> {
+        frame_props::get_bool(&self.props(), frame_props::SCENE_CHANGE_PREV)
+    }
+
+    /// Sets whether the frame is the first one of a scene (`_SceneChangePrev`).
+    #[inline]
+    pub fn set_scene_change_prev(&mut self, x: bool) -> frame_props::Result<()> {
+        frame_props::set_bool(&mut self.props_mut(), frame_props::SCENE_CHANGE_PREV, x)
+    }
 }
@@ -0,0 +1,610 @@
+//! Reserved VapourSynth frame properties.
+//!
+//! The frame properties starting with an underscore are reserved by VapourSynth and have a
+//! meaning defined by the
+//! [specification](https://www.vapoursynth.com/doc/apireference.html#reserved-frame-properties).
+//! This module contains the types for these properties. They are read and written with the
+//! typed accessors on `Frame`, such as `Frame::matrix()` and `Frame::set_matrix()`.
+
+use std::result;
+
+use thiserror::Error;
+
+use crate::map::{self, Map, ValueType};
+
+#[cfg(feature = "macros")]
+pub use vapoursynth_macros::FrameProps;
+
+/// The error type for reserved frame property operations.
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Error {
+    #[error("Couldn't access the property")]
+    Map(#[from] map::Error),
+    #[error("The value {value} of the {key} property is invalid")]
+    InvalidValue { key: &'static str, value: i64 },
+    #[error("The value of the {key} property is invalid")]
+    InvalidData { key: &'static str },
+    #[error("Couldn't access the {key} property")]
+    Property {
+        key: &'static str,
+        #[source]
+        source: map::Error,
+    },
+}
+
+/// A specialized `Result` type for reserved frame property operations.
+pub type Result<T> = result::Result<T, Error>;
+
+/// The `_Matrix` property key.
+pub const MATRIX: &str = "_Matrix";
+/// The `_Transfer` property key.
+pub const TRANSFER: &str = "_Transfer";
+/// The `_Primaries` property key.
+pub const PRIMARIES: &str = "_Primaries";
+/// The `_ColorRange` property key.
+pub const COLOR_RANGE: &str = "_ColorRange";
+/// The `_ChromaLocation` property key.
+pub const CHROMA_LOCATION: &str = "_ChromaLocation";
+/// The `_FieldBased` property key.
+pub const FIELD_BASED: &str = "_FieldBased";
+/// The `_SARNum` property key.
+pub const SAR_NUM: &str = "_SARNum";
+/// The `_SARDen` property key.
+pub const SAR_DEN: &str = "_SARDen";
+/// The `_DurationNum` property key.
+pub const DURATION_NUM: &str = "_DurationNum";
+/// The `_DurationDen` property key.
+pub const DURATION_DEN: &str = "_DurationDen";
+/// The `_PictType` property key.
+pub const PICT_TYPE: &str = "_PictType";
+/// The `_Combed` property key.
+pub const COMBED: &str = "_Combed";
+/// The `_SceneChangePrev` property key.
+pub const SCENE_CHANGE_PREV: &str = "_SceneChangePrev";
+/// The `_SceneChangeNext` property key.
+pub const SCENE_CHANGE_NEXT: &str = "_SceneChangeNext";
+
+/// A structure which can be read from and written to frame properties.
+///
+/// With the `macros` feature enabled, this trait can be derived for structs with named fields.
+/// Each field is stored in the property named after the field, which can be changed with the
+/// `#[frame_props(rename = "...")]` attribute. Field types must implement `PropValue`. Fields of
+/// type `Option<T>` are optional: missing properties are read as `None`, and `None` deletes the
+/// property on write.
+///
+/// # Example
+/// ```no_run
+/// # #[cfg(feature = "macros")]
+/// # {
+/// use vapoursynth::frame_props::FrameProps;
+///
+/// #[derive(FrameProps)]
+/// struct Stats {
+///     #[frame_props(rename = "PlaneStatsAverage")]
+///     average: f64,
+///     scene_score: Option<i64>,
+///     planes: Vec<i64>,
+/// }
+/// # }
+/// ```
+pub trait FrameProps: Sized {
+    /// Reads the structure from the map.
+    fn read_from(map: &Map) -> Result<Self>;
+
+    /// Writes the structure into the map, replacing existing values of the properties.
+    fn write_to(&self, map: &mut Map) -> Result<()>;
+}
+
+/// A value of a single frame property.
+pub trait PropValue: Sized {
+    /// Reads the value of the property with the given key.
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self>;
+
+    /// Writes the value into the property with the given key, replacing its existing values.
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()>;
+}
+
+/// A reserved frame property stored as an integer with a fixed set of valid values.
+pub trait IntProperty: Copy + Into<i64> + TryFrom<i64, Error = i64> {
+    /// The property key.
+    const KEY: &'static str;
+}
+
+macro_rules! int_property {
+    (
+        $(#[$attr:meta])*
+        pub enum $name:ident($key:expr) {
+            $($(#[$variant_attr:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+        pub enum $name {
+            $($(#[$variant_attr])* $variant = $value,)*
+        }
+
+        impl TryFrom<i64> for $name {
+            type Error = i64;
+
+            #[inline]
+            fn try_from(x: i64) -> result::Result<Self, i64> {
+                match x {
+                    $($value => Ok($name::$variant),)*
+                    _ => Err(x),
+                }
+            }
+        }
+
+        impl From<$name> for i64 {
+            #[inline]
+            fn from(x: $name) -> Self {
+                x as i64
+            }
+        }
+
+        impl IntProperty for $name {
+            const KEY: &'static str = $key;
+        }
+
+        impl PropValue for $name {
+            #[inline]
+            fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+                let x = map.get_int(key).map_err(keyed(key))?;
+                $name::try_from(x).map_err(|value| Error::InvalidValue { key, value })
+            }
+
+            #[inline]
+            fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+                map.set_int(key, i64::from(*self)).map_err(keyed(key))
+            }
+        }
+    };
+}
+
+int_property! {
+    /// Matrix coefficients (`_Matrix`), as defined in ITU-T H.273.
+    pub enum MatrixCoefficients(MATRIX) {
+        RGB = 0,
+        BT709 = 1,
+        Unspecified = 2,
+        FCC = 4,
+        BT470BG = 5,
+        /// Equivalent to BT.601.
+        ST170M = 6,
+        ST240M = 7,
+        YCgCo = 8,
+        BT2020NCL = 9,
+        BT2020CL = 10,
+        ChromaticityDerivedNCL = 12,
+        ChromaticityDerivedCL = 13,
+        ICtCp = 14,
+    }
+}
+
+int_property! {
+    /// Transfer characteristics (`_Transfer`), as defined in ITU-T H.273.
+    pub enum TransferCharacteristics(TRANSFER) {
+        BT709 = 1,
+        Unspecified = 2,
+        BT470M = 4,
+        BT470BG = 5,
+        /// Equivalent to BT.709.
+        BT601 = 6,
+        ST240M = 7,
+        Linear = 8,
+        Log100 = 9,
+        Log316 = 10,
+        IEC61966_2_4 = 11,
+        /// sRGB.
+        IEC61966_2_1 = 13,
+        /// Equivalent to BT.709.
+        BT2020_10 = 14,
+        /// Equivalent to BT.709.
+        BT2020_12 = 15,
+        /// Perceptual quantizer (PQ).
+        ST2084 = 16,
+        ST428 = 17,
+        /// Hybrid log-gamma (HLG).
+        AribB67 = 18,
+    }
+}
+
+int_property! {
+    /// Color primaries (`_Primaries`), as defined in ITU-T H.273.
+    pub enum ColorPrimaries(PRIMARIES) {
+        BT709 = 1,
+        Unspecified = 2,
+        BT470M = 4,
+        BT470BG = 5,
+        ST170M = 6,
+        ST240M = 7,
+        Film = 8,
+        BT2020 = 9,
+        /// CIE 1931 XYZ.
+        ST428 = 10,
+        /// DCI-P3.
+        ST431_2 = 11,
+        /// Display P3.
+        ST432_1 = 12,
+        EBU3213E = 22,
+    }
+}
+
+int_property! {
+    /// Color range (`_ColorRange`).
+    pub enum ColorRange(COLOR_RANGE) {
+        /// Full range (PC).
+        Full = 0,
+        /// Limited range (TV).
+        Limited = 1,
+    }
+}
+
+int_property! {
+    /// Chroma sample position (`_ChromaLocation`) in YUV formats.
+    pub enum ChromaLocation(CHROMA_LOCATION) {
+        Left = 0,
+        Center = 1,
+        TopLeft = 2,
+        Top = 3,
+        BottomLeft = 4,
+        Bottom = 5,
+    }
+}
+
+int_property! {
+    /// Field order (`_FieldBased`).
+    pub enum FieldBased(FIELD_BASED) {
+        /// The frame is progressive.
+        Progressive = 0,
+        /// The frame is interlaced with the bottom field first.
+        BottomFieldFirst = 1,
+        /// The frame is interlaced with the top field first.
+        TopFieldFirst = 2,
+    }
+}
+
+/// Picture type (`_PictType`) of a frame, as reported by the source decoder.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PictType {
+    I,
+    P,
+    B,
+    /// Any other picture type, for formats with additional frame types.
+    ///
+    /// Never holds `b'I'`, `b'P'` or `b'B'` when created with `from_byte()`.
+    Other(u8),
+}
+
+impl PictType {
+    /// Creates a picture type from the character stored in the property.
+    #[inline]
+    pub fn from_byte(x: u8) -> Self {
+        match x {
+            b'I' => PictType::I,
+            b'P' => PictType::P,
+            b'B' => PictType::B,
+            x => PictType::Other(x),
+        }
+    }
+
+    /// Returns the character stored in the property.
+    #[inline]
+    pub fn as_byte(self) -> u8 {
+        match self {
+            PictType::I => b'I',
+            PictType::P => b'P',
+            PictType::B => b'B',
+            PictType::Other(x) => x,
+        }
+    }
+
+    /// Parses the property value, which must be a single character.
+    #[inline]
+    fn from_bytes(x: &[u8]) -> Option<Self> {
+        match *x {
+            [x] => Some(Self::from_byte(x)),
+            _ => None,
+        }
+    }
+
+    /// Writes the property value, as a string if the character is ASCII.
+    #[inline]
+    fn write(self, map: &mut Map, key: &str) -> map::Result<()> {
+        let x = [self.as_byte()];
+        match std::str::from_utf8(&x) {
+            Ok(x) => map.set_str(key, x),
+            Err(_) => map.set_data(key, &x),
+        }
+    }
+}
+
+/// A rational number stored in a pair of reserved properties, like the sample aspect ratio or
+/// the frame duration.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Rational {
+    /// The numerator, greater than or equal to 0.
+    pub numerator: i64,
+
+    /// The denominator, greater than 0.
+    pub denominator: i64,
+}
+
+/// Returns a function which adds the key to a map error.
+#[inline]
+fn keyed(key: &'static str) -> impl FnOnce(map::Error) -> Error {
+    move |source| Error::Property { key, source }
+}
+
+/// Converts `Ok` into `Ok(Some)` and `Err(KeyNotFound)` into `Ok(None)`.
+#[inline]
+fn optional<T>(x: map::Result<T>) -> Result<Option<T>> {
+    match x {
+        Ok(x) => Ok(Some(x)),
+        Err(map::Error::KeyNotFound) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Checks the rational number components against the specification.
+#[inline]
+fn validate_rational(keys: (&'static str, &'static str), x: Rational) -> Result<()> {
+    if x.numerator < 0 {
+        return Err(Error::InvalidValue {
+            key: keys.0,
+            value: x.numerator,
+        });
+    }
+    if x.denominator <= 0 {
+        return Err(Error::InvalidValue {
+            key: keys.1,
+            value: x.denominator,
+        });
+    }
+    Ok(())
+}
+
+/// Retrieves an integer property with a fixed set of valid values.
+pub(crate) fn get_int_property<T: IntProperty>(map: &Map) -> Result<Option<T>> {
+    optional(map.get_int(T::KEY))?
+        .map(|x| T::try_from(x).map_err(|value| Error::InvalidValue { key: T::KEY, value }))
+        .transpose()
+}
+
+/// Sets an integer property with a fixed set of valid values.
+#[inline]
+pub(crate) fn set_int_property<T: IntProperty>(map: &mut Map, x: T) -> Result<()> {
+    map.set_int(T::KEY, x.into())?;
+    Ok(())
+}
+
+/// Retrieves a boolean property, which must be either 0 or 1.
+pub(crate) fn get_bool(map: &Map, key: &'static str) -> Result<Option<bool>> {
+    optional(map.get_int(key))?
+        .map(|x| match x {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(Error::InvalidValue { key, value }),
+        })
+        .transpose()
+}
+
+/// Sets a boolean property.
+#[inline]
+pub(crate) fn set_bool(map: &mut Map, key: &'static str, x: bool) -> Result<()> {
+    map.set_int(key, i64::from(x))?;
+    Ok(())
+}
+
+/// Retrieves a rational property stored in two keys.
+///
+/// Returns `None` if either of the keys is missing.
+pub(crate) fn get_rational(
+    map: &Map,
+    keys: (&'static str, &'static str),
+) -> Result<Option<Rational>> {
+    let numerator = optional(map.get_int(keys.0))?;
+    let denominator = optional(map.get_int(keys.1))?;
+
+    match (numerator, denominator) {
+        (Some(numerator), Some(denominator)) => {
+            let x = Rational {
+                numerator,
+                denominator,
+            };
+            validate_rational(keys, x)?;
+            Ok(Some(x))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Sets a rational property stored in two keys.
+pub(crate) fn set_rational(
+    map: &mut Map,
+    keys: (&'static str, &'static str),
+    x: Rational,
+) -> Result<()> {
+    validate_rational(keys, x)?;
+    map.set_int(keys.0, x.numerator)?;
+    map.set_int(keys.1, x.denominator)?;
+    Ok(())
+}
+
+/// Retrieves the picture type property.
+pub(crate) fn get_pict_type(map: &Map) -> Result<Option<PictType>> {
+    optional(map.get_data(PICT_TYPE))?
+        .map(|x| PictType::from_bytes(x).ok_or(Error::InvalidData { key: PICT_TYPE }))
+        .transpose()
+}
+
+/// Sets the picture type property.
+#[inline]
+pub(crate) fn set_pict_type(map: &mut Map, x: PictType) -> Result<()> {
+    x.write(map, PICT_TYPE)?;
+    Ok(())
+}
+
+impl PropValue for i64 {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        map.get_int(key).map_err(keyed(key))
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        map.set_int(key, *self).map_err(keyed(key))
+    }
+}
+
+macro_rules! impl_prop_value_narrow_int {
+    ($($type:ty),*) => {
+        $(
+            impl PropValue for $type {
+                #[inline]
+                fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+                    let x = map.get_int(key).map_err(keyed(key))?;
+                    <$type>::try_from(x).map_err(|_| Error::InvalidValue { key, value: x })
+                }
+
+                #[inline]
+                fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+                    map.set_int(key, i64::from(*self)).map_err(keyed(key))
+                }
+            }
+        )*
+    };
+}
+
+impl_prop_value_narrow_int!(i8, i16, i32, u8, u16, u32);
+
+impl PropValue for bool {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        match map.get_int(key).map_err(keyed(key))? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(Error::InvalidValue { key, value }),
+        }
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        map.set_int(key, i64::from(*self)).map_err(keyed(key))
+    }
+}
+
+impl PropValue for f64 {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        map.get_float(key).map_err(keyed(key))
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        map.set_float(key, *self).map_err(keyed(key))
+    }
+}
+
+impl PropValue for f32 {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        map.get_f32(key).map_err(keyed(key))
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        map.set_float(key, f64::from(*self)).map_err(keyed(key))
+    }
+}
+
+impl PropValue for String {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        map.get_str(key).map(str::to_owned).map_err(keyed(key))
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        map.set_str(key, self).map_err(keyed(key))
+    }
+}
+
+impl PropValue for PictType {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        let x = map.get_data(key).map_err(keyed(key))?;
+        PictType::from_bytes(x).ok_or(Error::InvalidData { key })
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        self.write(map, key).map_err(keyed(key))
+    }
+}
+
+impl PropValue for Vec<i64> {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        map.get_int_array(key)
+            .map(<[_]>::to_vec)
+            .map_err(keyed(key))
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        map.set_int_array(key, self).map_err(keyed(key))
+    }
+}
+
+impl PropValue for Vec<f64> {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        map.get_float_array(key)
+            .map(<[_]>::to_vec)
+            .map_err(keyed(key))
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        map.set_float_array(key, self).map_err(keyed(key))
+    }
+}
+
+impl PropValue for Vec<String> {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        let iter = map.get_str_iter(key).map_err(keyed(key))?;
+        Ok(iter.map(str::to_owned).collect())
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        map.set_empty(key, ValueType::Data).map_err(keyed(key))?;
+        self.iter()
+            .try_for_each(|x| map.append_str(key, x))
+            .map_err(keyed(key))
+    }
+}
+
+impl<T: PropValue> PropValue for Option<T> {
+    #[inline]
+    fn read_prop(map: &Map, key: &'static str) -> Result<Self> {
+        match map.value_count(key) {
+            Ok(_) => T::read_prop(map, key).map(Some),
+            Err(map::Error::KeyNotFound) => Ok(None),
+            Err(e) => Err(keyed(key)(e)),
+        }
+    }
+
+    #[inline]
+    fn write_prop(&self, map: &mut Map, key: &'static str) -> Result<()> {
+        match self {
+            Some(x) => x.write_prop(map, key),
+            None => match map.delete_key(key) {
+                Ok(()) | Err(map::Error::KeyNotFound) => Ok(()),
+                Err(e) => Err(keyed(key)(e)),
+            },
+        }
+    }
+}
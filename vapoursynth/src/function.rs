@@ -3,13 +3,36 @@
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::os::raw::c_void;
+use std::panic::AssertUnwindSafe;
 use std::ptr::NonNull;
-use std::{mem, panic, process};
+use std::{mem, panic};
+use thiserror::Error;
 use vapoursynth_sys as ffi;
 
 use crate::api::API;
 use crate::core::CoreRef;
-use crate::map::{Map, MapRef, MapRefMut};
+use crate::map::{self, Map, MapRef, MapRefMut, OwnedMap, ToMapValues};
+use crate::plugins::ffi::panic_message;
+use crate::plugins::{ArgumentError, FilterParameter};
+
+/// The key VapourSynth uses for the return value of functions passed to filters like
+/// `std.FrameEval`.
+const RETURN_KEY: &str = "val";
+
+/// The error type for `Function::call_typed()`.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Couldn't store argument {key}")]
+    Argument {
+        key: &'static str,
+        #[source]
+        source: map::Error,
+    },
+    #[error("The function returned an error: {0}")]
+    Call(String),
+    #[error("Couldn't get the return value")]
+    ReturnValue(#[source] ArgumentError),
+}
 
 /// Holds a reference to a function that may be called.
 #[derive(Debug)]
@@ -60,9 +83,18 @@ impl<'core> Function<'core> {
         self.handle.as_ptr()
     }
 
+    /// Returns the underlying pointer, transferring the reference to the caller.
+    #[inline]
+    pub(crate) fn into_ptr(self) -> *mut ffi::VSFunction {
+        let ptr = self.ptr();
+        mem::forget(self);
+        ptr
+    }
+
     /// Creates a new function.
     ///
-    /// To indicate an error from the callback, set an error on the output map.
+    /// To indicate an error from the callback, set an error on the output map. If the callback
+    /// panics, the panic message is set as the error.
     #[inline]
     pub fn new<F>(_api: API, core: CoreRef<'core>, callback: F) -> Self
     where
@@ -77,20 +109,23 @@ impl<'core> Function<'core> {
         ) where
             F: Fn(API, CoreRef<'core>, &Map<'core>, &mut Map<'core>) + Send + Sync + 'core,
         {
+            let callback = &*(user_data as *const F);
+
             let closure = move || {
                 let api = API::get_cached();
                 let core = CoreRef::from_ptr(core);
                 let in_ = MapRef::from_ptr(in_);
                 let mut out = MapRefMut::from_ptr(out);
-                let callback = Box::from_raw(user_data as *mut F);
 
                 callback(api, core, &in_, &mut out);
-
-                mem::forget(callback);
             };
 
-            if panic::catch_unwind(closure).is_err() {
-                process::abort();
+            // The callback is only accessed through a shared reference, so it's up to it to stay
+            // consistent if it panics, like with any `Sync` type.
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(closure)) {
+                let message = format!("The function panicked: {}", panic_message(&*payload));
+                let mut out = MapRefMut::from_ptr(out);
+                out.set_error(&message.replace('\0', "\\0")).unwrap();
             }
         }
 
@@ -121,3 +156,193 @@ impl<'core> Function<'core> {
         unsafe { API::get_cached().call_func(self.handle.as_ptr(), in_.deref(), out.deref_mut()) };
     }
 }
+
+impl<'core> Function<'core> {
+    /// Creates a new function from a closure with typed arguments and return value.
+    ///
+    /// `names` are the names of the arguments, in the order of the closure parameters. They have to
+    /// be given explicitly because the parameter names of a closure aren't part of its type, so
+    /// they can't be recovered from `callback`.
+    ///
+    /// The arguments are decoded with `FilterParameter`, like filter arguments, so `Option<T>`
+    /// parameters are optional and `Vec<T>` parameters receive all values of the argument. The
+    /// return value is stored under the `val` key, which is where filters like `std.FrameEval` and
+    /// `std.ModifyFrame` look for it. Returning `()` stores nothing. Errors are set on the output
+    /// map.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use vapoursynth::prelude::*;
+    /// # use vapoursynth::core::CoreRef;
+    /// use vapoursynth::function::Function;
+    ///
+    /// # fn foo<'core>(core: CoreRef<'core>, clips: Vec<Node<'core>>) {
+    /// let select = Function::typed(core, ["n"], move |n: i64| -> anyhow::Result<Node> {
+    ///     Ok(clips[n as usize % clips.len()].clone())
+    /// });
+    /// # }
+    /// ```
+    pub fn typed<Args, F, const N: usize>(
+        core: CoreRef<'core>,
+        names: [&'static str; N],
+        callback: F,
+    ) -> Self
+    where
+        F: TypedCallback<'core, Args, N>,
+    {
+        let api = unsafe { API::get_cached() };
+        Self::new(api, core, move |_api, _core, in_, out| {
+            if let Err(err) = callback.invoke(&names, in_, out) {
+                let message = format!("{:#}", err).replace('\0', "\\0");
+                out.set_error(&message).unwrap();
+            }
+        })
+    }
+
+    /// Calls the function with typed arguments and return value.
+    ///
+    /// `names` are the names of the arguments in `args`, which is a tuple of values implementing
+    /// `ToMapValues`. The return value is read from the `val` key; use `()` to ignore it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use vapoursynth::function::Function;
+    /// # fn foo(function: Function) -> Result<(), vapoursynth::function::Error> {
+    /// let sum: i64 = function.call_typed(["a", "b"], (1, 2))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_typed<A, R, const N: usize>(
+        &self,
+        names: [&'static str; N],
+        args: A,
+    ) -> Result<R, Error>
+    where
+        A: TypedArgs<'core, N>,
+        R: ReturnValue<'core>,
+    {
+        let api = unsafe { API::get_cached() };
+        let mut in_ = OwnedMap::new(api);
+        let mut out = OwnedMap::new(api);
+
+        args.store(&names, &mut in_)?;
+        self.call(&in_, &mut out);
+
+        if let Some(error) = out.error() {
+            return Err(Error::Call(error.into_owned()));
+        }
+
+        R::load(&out, RETURN_KEY).map_err(Error::ReturnValue)
+    }
+}
+
+/// A closure which can be wrapped in a `Function` by `Function::typed()`.
+///
+/// This is implemented for closures taking up to 8 arguments implementing `FilterParameter` and
+/// returning `anyhow::Result<R>` where `R` implements `ReturnValue`.
+pub trait TypedCallback<'core, Args, const N: usize>: Send + Sync + 'core {
+    /// Decodes the arguments from `in_`, calls the closure and stores its return value in `out`.
+    fn invoke(
+        &self,
+        names: &[&'static str; N],
+        in_: &Map<'core>,
+        out: &mut Map<'core>,
+    ) -> anyhow::Result<()>;
+}
+
+/// A tuple of arguments for `Function::call_typed()`.
+///
+/// This is implemented for tuples of up to 8 values implementing `ToMapValues`.
+pub trait TypedArgs<'core, const N: usize> {
+    /// Stores the arguments in `map` under the given names.
+    fn store(&self, names: &[&'static str; N], map: &mut Map<'core>) -> Result<(), Error>;
+}
+
+/// A return value of a typed function.
+pub trait ReturnValue<'core>: Sized {
+    /// Stores the value in `map`.
+    fn store(&self, map: &mut Map<'core>, key: &str) -> map::Result<()>;
+
+    /// Retrieves the value from `map`.
+    fn load(map: &Map<'core>, key: &'static str) -> Result<Self, ArgumentError>;
+}
+
+impl<'core> ReturnValue<'core> for () {
+    #[inline]
+    fn store(&self, _map: &mut Map<'core>, _key: &str) -> map::Result<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn load(_map: &Map<'core>, _key: &'static str) -> Result<Self, ArgumentError> {
+        Ok(())
+    }
+}
+
+impl<'core, T> ReturnValue<'core> for T
+where
+    T: ToMapValues<'core> + for<'map> FilterParameter<'map, 'core>,
+{
+    #[inline]
+    fn store(&self, map: &mut Map<'core>, key: &str) -> map::Result<()> {
+        self.insert_into(map, key)
+    }
+
+    #[inline]
+    fn load(map: &Map<'core>, key: &'static str) -> Result<Self, ArgumentError> {
+        T::get_from_map(map, key)
+    }
+}
+
+macro_rules! impl_typed {
+    ($n:expr; $($arg:ident $index:tt),*) => {
+        impl<'core, F, R, $($arg),*> TypedCallback<'core, ($($arg,)*), $n> for F
+        where
+            F: Fn($($arg),*) -> anyhow::Result<R> + Send + Sync + 'core,
+            R: ReturnValue<'core>,
+            $($arg: for<'map> FilterParameter<'map, 'core>,)*
+        {
+            #[inline]
+            #[allow(unused_variables)]
+            fn invoke(
+                &self,
+                names: &[&'static str; $n],
+                in_: &Map<'core>,
+                out: &mut Map<'core>,
+            ) -> anyhow::Result<()> {
+                let rv = self($($arg::get_from_map(in_, names[$index])?),*)?;
+                rv.store(out, RETURN_KEY)?;
+                Ok(())
+            }
+        }
+
+        impl<'core, $($arg),*> TypedArgs<'core, $n> for ($($arg,)*)
+        where
+            $($arg: ToMapValues<'core>,)*
+        {
+            #[inline]
+            #[allow(unused_variables)]
+            fn store(&self, names: &[&'static str; $n], map: &mut Map<'core>) -> Result<(), Error> {
+                $(
+                    self.$index
+                        .insert_into(map, names[$index])
+                        .map_err(|source| Error::Argument {
+                            key: names[$index],
+                            source,
+                        })?;
+                )*
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_typed!(0;);
+impl_typed!(1; A 0);
+impl_typed!(2; A 0, B 1);
+impl_typed!(3; A 0, B 1, C 2);
+impl_typed!(4; A 0, B 1, C 2, D 3);
+impl_typed!(5; A 0, B 1, C 2, D 3, E 4);
+impl_typed!(6; A 0, B 1, C 2, D 3, E 4, G 5);
+impl_typed!(7; A 0, B 1, C 2, D 3, E 4, G 5, H 6);
+impl_typed!(8; A 0, B 1, C 2, D 3, E 4, G 5, H 6, I 7);
@@ -47,6 +47,9 @@
 //! the `make_filter_function!` macro. Finally, put `export_vapoursynth_plugin!` at the top level
 //! of `src/lib.rs` to export the functionality.
 //!
+//! With the `macros` feature enabled, the `#[filter_function]` and `#[plugin]` attribute macros
+//! can be used instead of `make_filter_function!` and `export_vapoursynth_plugin!`.
+//!
 //! **Important note:** due to what seems to be a
 //! [bug](https://github.com/rust-lang/rust/issues/50176) in rustc, it's impossible to make plugins
 //! on the `i686-pc-windows-gnu` target (all other variations of `x86_64` and `i686` do work).
@@ -144,6 +147,9 @@
 
 #![allow(unsafe_op_in_unsafe_fn)]
 
+// Allows the procedural macros to refer to `::vapoursynth` from within this crate.
+extern crate self as vapoursynth;
+
 #[cfg(test)]
 pub extern crate vapoursynth_sys;
 
@@ -156,11 +162,13 @@ pub mod component;
 pub mod core;
 pub mod format;
 pub mod frame;
+pub mod frame_props;
 pub mod function;
 pub mod map;
 pub mod node;
 pub mod plugin;
 pub mod plugins;
+pub mod render;
 pub mod video_info;
 pub mod vsscript;
 
@@ -172,7 +180,7 @@ pub mod prelude {
     pub use super::component::Component;
     pub use super::format::{ColorFamily, PresetFormat, SampleType};
     pub use super::frame::{Frame, FrameRef, FrameRefMut};
-    pub use super::map::{Map, OwnedMap, ValueType};
+    pub use super::map::{DataTypeHint, Map, MapValue, OwnedMap, ValueType};
     pub use super::node::{GetFrameError, Node};
     pub use super::plugin::Plugin;
     pub use super::video_info::Property;
@@ -181,4 +189,7 @@ pub mod prelude {
 
 pub use anyhow;
 
+#[cfg(feature = "macros")]
+pub use vapoursynth_macros::{filter_function, plugin};
+
 mod tests;
@@ -1,5 +1,6 @@
 use std::ffi::NulError;
 use std::result;
+use std::str::Utf8Error;
 
 use thiserror::Error;
 
@@ -16,6 +17,8 @@ pub enum Error {
     InvalidKey(#[from] InvalidKeyError),
     #[error("Couldn't convert to a CString")]
     CStringConversion(#[from] NulError),
+    #[error("The data isn't valid UTF-8")]
+    InvalidUtf8(#[from] Utf8Error),
     #[error("An unexpected error occurred")]
     UnknownError,
 }
@@ -42,6 +42,39 @@ impl<'map, 'elem> Iterator for Keys<'map, 'elem> {
 
 impl<'map, 'elem> ExactSizeIterator for Keys<'map, 'elem> {}
 
+/// An iterator over the keys and values of a map.
+#[derive(Debug, Clone, Copy)]
+pub struct Iter<'map, 'elem: 'map> {
+    keys: Keys<'map, 'elem>,
+}
+
+impl<'map, 'elem> Iter<'map, 'elem> {
+    #[inline]
+    pub(crate) fn new(map: &'map Map<'elem>) -> Self {
+        Self {
+            keys: Keys::new(map),
+        }
+    }
+}
+
+impl<'map, 'elem> Iterator for Iter<'map, 'elem> {
+    type Item = (&'map str, MapValue<'map, 'elem>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.keys.next()?;
+        let value = self.keys.map.get_any(key).unwrap();
+        Some((key, value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<'map, 'elem> ExactSizeIterator for Iter<'map, 'elem> {}
+
 /// An iterator over the values associated with a certain key of a map.
 #[derive(Debug, Clone)]
 pub struct ValueIter<'map, 'elem: 'map, T> {
@@ -54,6 +87,9 @@ pub struct ValueIter<'map, 'elem: 'map, T> {
 
 macro_rules! impl_value_iter {
     ($value_type:path, $type:ty, $func:ident) => {
+        impl_value_iter!($value_type, $type, $func, |_map, _key, _count| Ok(()));
+    };
+    ($value_type:path, $type:ty, $func:ident, $validate:expr) => {
         impl<'map, 'elem> $crate::map::ValueIter<'map, 'elem, $type> {
             /// Creates a `ValueIter` from the given `map` and `key`.
             ///
@@ -71,6 +107,15 @@ macro_rules! impl_value_iter {
                 };
 
                 let count = map.value_count_raw_unchecked(&key)? as i32;
+
+                // Check that retrieving the values can't fail, so the iterator can't fail either.
+                let validate: fn(
+                    &$crate::prelude::Map,
+                    &std::ffi::CStr,
+                    i32,
+                ) -> $crate::map::Result<()> = $validate;
+                validate(map, &key, count)?;
+
                 Ok(Self {
                     map,
                     key,
@@ -113,6 +158,18 @@ macro_rules! impl_value_iter {
 impl_value_iter!(ValueType::Int, i64, get_int_raw_unchecked);
 impl_value_iter!(ValueType::Float, f64, get_float_raw_unchecked);
 impl_value_iter!(ValueType::Data, &'map [u8], get_data_raw_unchecked);
+impl_value_iter!(
+    ValueType::Data,
+    &'map str,
+    get_str_raw_unchecked,
+    validate_str
+);
+impl_value_iter!(
+    ValueType::Data,
+    String,
+    get_string_raw_unchecked,
+    validate_str
+);
 impl_value_iter!(
     ValueType::VideoNode,
     Node<'elem>,
@@ -128,3 +185,13 @@ impl_value_iter!(
     Function<'elem>,
     get_function_raw_unchecked
 );
+
+/// Checks that all values associated with the key are valid UTF-8.
+///
+/// The key must be valid, this is only called from the unsafe `ValueIter::new()`.
+fn validate_str(map: &Map, key: &CStr, count: i32) -> Result<()> {
+    for index in 0..count {
+        unsafe { map.get_str_raw_unchecked(key, index) }?;
+    }
+    Ok(())
+}
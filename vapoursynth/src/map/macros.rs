@@ -0,0 +1,152 @@
+use super::*;
+
+/// Creates an `OwnedMap` with the given keys and values.
+///
+/// Evaluates to a `map::Result<OwnedMap>`, which is an error if any of the keys is invalid or
+/// any of the values can't be stored. Values are converted with `ToMapValues`: scalars are stored
+/// as a single value, and arrays and vectors as all of their elements. Note that `&[u8]` slices
+/// are stored as data, while arrays and vectors of integers are stored as integer arrays.
+///
+/// # Panics
+/// Panics if the VapourSynth API isn't available.
+///
+/// # Example
+/// ```no_run
+/// # use vapoursynth::prelude::*;
+/// # fn foo(node: Node) -> vapoursynth::map::Result<()> {
+/// let args = vapoursynth::map! {
+///     "clip" => &node,
+///     "width" => 1920,
+///     "kernel" => "spline36",
+///     "planes" => [0, 1],
+/// }?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! map {
+    ($($key:expr => $value:expr),* $(,)?) => {{
+        let api = $crate::api::API::get().expect("VapourSynth API not available");
+        #[allow(unused_mut)]
+        let mut map = $crate::map::OwnedMap::new(api);
+        let rv: $crate::map::Result<()> = Ok(());
+        rv$(.and_then(|()| $crate::map::ToMapValues::insert_into(&$value, &mut map, $key)))*
+            .map(|()| map)
+    }};
+}
+
+/// A trait for single values which can be stored in a map by `ToMapValues`.
+pub trait ToMapValue<'elem> {
+    /// The type of the value in the map.
+    const VALUE_TYPE: ValueType;
+
+    /// Appends the value to the map.
+    fn append_to(&self, map: &mut Map<'elem>, key: &str) -> Result<()>;
+}
+
+/// A trait for values or arrays of values which can be stored in a map.
+///
+/// This is used by the `map!` macro.
+pub trait ToMapValues<'elem> {
+    /// Appends the values to the map.
+    fn append_to(&self, map: &mut Map<'elem>, key: &str) -> Result<()>;
+
+    /// Stores the values in the map, replacing any existing values of the key.
+    fn insert_into(&self, map: &mut Map<'elem>, key: &str) -> Result<()> {
+        Map::is_key_valid(key)?;
+        match map.delete_key(key) {
+            Ok(()) | Err(Error::KeyNotFound) => {}
+            Err(e) => return Err(e),
+        }
+        self.append_to(map, key)
+    }
+}
+
+macro_rules! impl_to_map_value {
+    ($type:ty, $value_type:expr, $func:ident, $convert:path) => {
+        impl_to_map_value!($type, $value_type, |map, key, x| {
+            map.$func(key, $convert(*x))
+        });
+    };
+    ($type:ty, $value_type:expr, $func:ident) => {
+        impl_to_map_value!($type, $value_type, |map, key, x| map.$func(key, x));
+    };
+    ($type:ty, $value_type:expr, |$map:ident, $key:ident, $x:ident| $append:expr) => {
+        impl<'elem> ToMapValue<'elem> for $type {
+            const VALUE_TYPE: ValueType = $value_type;
+
+            #[inline]
+            fn append_to(&self, $map: &mut Map<'elem>, $key: &str) -> Result<()> {
+                let $x = self;
+                $append
+            }
+        }
+
+        impl<'elem> ToMapValues<'elem> for $type {
+            #[inline]
+            fn append_to(&self, map: &mut Map<'elem>, key: &str) -> Result<()> {
+                ToMapValue::append_to(self, map, key)
+            }
+        }
+    };
+}
+
+impl_to_map_value!(i8, ValueType::Int, append_int, i64::from);
+impl_to_map_value!(i16, ValueType::Int, append_int, i64::from);
+impl_to_map_value!(i32, ValueType::Int, append_int, i64::from);
+impl_to_map_value!(i64, ValueType::Int, append_int, i64::from);
+impl_to_map_value!(u8, ValueType::Int, append_int, i64::from);
+impl_to_map_value!(u16, ValueType::Int, append_int, i64::from);
+impl_to_map_value!(u32, ValueType::Int, append_int, i64::from);
+impl_to_map_value!(bool, ValueType::Int, append_int, i64::from);
+impl_to_map_value!(f32, ValueType::Float, append_float, f64::from);
+impl_to_map_value!(f64, ValueType::Float, append_float, f64::from);
+impl_to_map_value!(&str, ValueType::Data, append_str);
+impl_to_map_value!(String, ValueType::Data, append_str);
+impl_to_map_value!(&[u8], ValueType::Data, append_data);
+impl_to_map_value!(Node<'elem>, ValueType::VideoNode, append_node);
+impl_to_map_value!(FrameRef<'elem>, ValueType::VideoFrame, append_frame);
+impl_to_map_value!(Function<'elem>, ValueType::Function, append_function);
+
+impl<'elem, T: ToMapValue<'elem>> ToMapValue<'elem> for &T {
+    const VALUE_TYPE: ValueType = T::VALUE_TYPE;
+
+    #[inline]
+    fn append_to(&self, map: &mut Map<'elem>, key: &str) -> Result<()> {
+        T::append_to(self, map, key)
+    }
+}
+
+impl<'elem, T: ToMapValues<'elem>> ToMapValues<'elem> for &T {
+    #[inline]
+    fn append_to(&self, map: &mut Map<'elem>, key: &str) -> Result<()> {
+        T::append_to(self, map, key)
+    }
+}
+
+/// Appends all elements, or creates an empty array of the element type.
+fn append_all<'elem, T: ToMapValue<'elem>>(
+    values: &[T],
+    map: &mut Map<'elem>,
+    key: &str,
+) -> Result<()> {
+    if values.is_empty() && map.value_count(key) == Err(Error::KeyNotFound) {
+        return map.set_empty(key, T::VALUE_TYPE);
+    }
+
+    values.iter().try_for_each(|x| x.append_to(map, key))
+}
+
+impl<'elem, T: ToMapValue<'elem>, const N: usize> ToMapValues<'elem> for [T; N] {
+    #[inline]
+    fn append_to(&self, map: &mut Map<'elem>, key: &str) -> Result<()> {
+        append_all(self, map, key)
+    }
+}
+
+impl<'elem, T: ToMapValue<'elem>> ToMapValues<'elem> for Vec<T> {
+    #[inline]
+    fn append_to(&self, map: &mut Map<'elem>, key: &str) -> Result<()> {
+        append_all(self, map, key)
+    }
+}
@@ -5,7 +5,7 @@ use std::ffi::{CStr, CString};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::ptr::NonNull;
-use std::{result, slice};
+use std::{fmt, result, slice, str};
 use vapoursynth_sys as ffi;
 
 use crate::api::API;
@@ -17,16 +17,23 @@ mod errors;
 pub use self::errors::{Error, InvalidKeyError, Result};
 
 mod iterators;
-pub use self::iterators::{Keys, ValueIter};
+pub use self::iterators::{Iter, Keys, ValueIter};
+
+mod macros;
+pub use self::macros::{ToMapValue, ToMapValues};
+
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "serde")]
+pub use self::serde::{DeserializeError, from_map};
 
 mod value;
-pub use self::value::{Value, ValueType};
+pub use self::value::{DataTypeHint, MapValue, Value, ValueType};
 
 /// A VapourSynth map.
 ///
 /// A map contains key-value pairs where the value is zero or more elements of a certain type.
 // This type is intended to be publicly used only in reference form.
-#[derive(Debug)]
 pub struct Map<'elem> {
     // The actual mutability of this depends on whether it's accessed via `&Map` or `&mut Map`.
     handle: NonNull<ffi::VSMap>,
@@ -34,7 +41,6 @@ pub struct Map<'elem> {
 }
 
 /// A reference to a VapourSynth map.
-#[derive(Debug)]
 pub struct MapRef<'owner, 'elem> {
     // Only immutable references to this are allowed.
     map: Map<'elem>,
@@ -42,7 +48,6 @@ pub struct MapRef<'owner, 'elem> {
 }
 
 /// A reference to a mutable VapourSynth map.
-#[derive(Debug)]
 pub struct MapRefMut<'owner, 'elem> {
     // Both mutable and immutable references to this are allowed.
     map: Map<'elem>,
@@ -50,7 +55,6 @@ pub struct MapRefMut<'owner, 'elem> {
 }
 
 /// An owned VapourSynth map.
-#[derive(Debug)]
 pub struct OwnedMap<'elem> {
     // Both mutable and immutable references to this are allowed.
     map: Map<'elem>,
@@ -59,6 +63,49 @@ pub struct OwnedMap<'elem> {
 unsafe impl<'elem> Send for Map<'elem> {}
 unsafe impl<'elem> Sync for Map<'elem> {}
 
+impl fmt::Debug for Map<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl fmt::Debug for MapRef<'_, '_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl fmt::Debug for MapRefMut<'_, '_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl fmt::Debug for OwnedMap<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<'elem> PartialEq for Map<'elem> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key_count() == other.key_count()
+            && self
+                .iter()
+                .all(|(key, value)| other.get_any(key).is_ok_and(|x| x == value))
+    }
+}
+
+impl<'elem> PartialEq for OwnedMap<'elem> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
 #[doc(hidden)]
 impl<'elem> Deref for Map<'elem> {
     type Target = ffi::VSMap;
@@ -130,6 +177,15 @@ impl<'elem> DerefMut for OwnedMap<'elem> {
     }
 }
 
+impl<'elem> Clone for OwnedMap<'elem> {
+    #[inline]
+    fn clone(&self) -> Self {
+        let mut map = unsafe { Self::from_ptr(API::get_cached().create_map()) };
+        map.merge_from(self);
+        map
+    }
+}
+
 impl<'elem> OwnedMap<'elem> {
     /// Creates a new map.
     #[inline]
@@ -260,6 +316,16 @@ impl<'elem> Map<'elem> {
         }
     }
 
+    /// Copies all keys and values from `other` into this map.
+    ///
+    /// Values of keys which exist in both maps are replaced with the values from `other`.
+    #[inline]
+    pub fn merge_from(&mut self, other: &Map<'elem>) {
+        unsafe {
+            API::get_cached().copy_map(other, self);
+        }
+    }
+
     /// Returns the error message contained in the map, if any.
     #[inline]
     pub fn error(&self) -> Option<Cow<'_, str>> {
@@ -317,6 +383,12 @@ impl<'elem> Map<'elem> {
         Keys::new(self)
     }
 
+    /// Returns an iterator over all keys and values in a map.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, 'elem> {
+        Iter::new(self)
+    }
+
     /// Returns the number of elements associated with a key in a map.
     ///
     /// # Safety
@@ -366,6 +438,59 @@ impl<'elem> Map<'elem> {
         unsafe { self.value_type_raw_unchecked(&key) }
     }
 
+    /// Retrieves all values associated with the key, whatever their type.
+    pub fn get_any<'map>(&'map self, key: &str) -> Result<MapValue<'map, 'elem>> {
+        let key = Map::make_raw_key(key)?;
+        unsafe { self.get_any_raw_unchecked(&key) }
+    }
+
+    /// Retrieves all values associated with the key, whatever their type.
+    ///
+    /// # Safety
+    /// The caller must ensure `key` is valid.
+    pub(crate) unsafe fn get_any_raw_unchecked<'map>(
+        &'map self,
+        key: &CStr,
+    ) -> Result<MapValue<'map, 'elem>> {
+        let value_type = unsafe { self.value_type_raw_unchecked(key) }?;
+        let count = unsafe { self.value_count_raw_unchecked(key) }? as i32;
+
+        fn collect<T>(count: i32, f: impl FnMut(i32) -> Result<T>) -> Result<Vec<T>> {
+            (0..count).map(f).collect()
+        }
+
+        unsafe {
+            Ok(match value_type {
+                ValueType::Int => MapValue::Int(self.get_int_array_raw_unchecked(key)?),
+                ValueType::Float => MapValue::Float(self.get_float_array_raw_unchecked(key)?),
+                ValueType::Data => {
+                    let is_str = count > 0
+                        && (0..count).all(|i| {
+                            self.data_type_hint_raw_unchecked(key, i) == Ok(DataTypeHint::Utf8)
+                                && self.get_str_raw_unchecked(key, i).is_ok()
+                        });
+
+                    if is_str {
+                        MapValue::Str(collect(count, |i| self.get_str_raw_unchecked(key, i))?)
+                    } else {
+                        MapValue::Data(collect(count, |i| self.get_data_raw_unchecked(key, i))?)
+                    }
+                }
+                ValueType::VideoNode => MapValue::VideoNode(collect(count, |i| {
+                    self.get_video_node_raw_unchecked(key, i)
+                })?),
+                ValueType::AudioNode => MapValue::AudioNode(count as usize),
+                ValueType::VideoFrame => MapValue::VideoFrame(collect(count, |i| {
+                    self.get_video_frame_raw_unchecked(key, i)
+                })?),
+                ValueType::AudioFrame => MapValue::AudioFrame(count as usize),
+                ValueType::Function => {
+                    MapValue::Function(collect(count, |i| self.get_function_raw_unchecked(key, i))?)
+                }
+            })
+        }
+    }
+
     /// Deletes the given key.
     ///
     /// # Safety
@@ -424,6 +549,15 @@ impl<'elem> Map<'elem> {
         unsafe { self.get_int_raw_unchecked(&key, 0) }
     }
 
+    /// Retrieves an integer from a map, saturated to the `i32` range.
+    ///
+    /// This function retrieves the first value associated with the key.
+    #[inline]
+    pub fn get_i32(&self, key: &str) -> Result<i32> {
+        let key = Map::make_raw_key(key)?;
+        unsafe { self.get_i32_raw_unchecked(&key, 0) }
+    }
+
     /// Retrieves integers from a map.
     #[inline]
     pub fn get_int_iter<'map>(&'map self, key: &str) -> Result<ValueIter<'map, 'elem, i64>> {
@@ -449,6 +583,15 @@ impl<'elem> Map<'elem> {
         unsafe { self.get_float_raw_unchecked(&key, 0) }
     }
 
+    /// Retrieves a floating point number from a map, saturated to the `f32` range.
+    ///
+    /// This function retrieves the first value associated with the key.
+    #[inline]
+    pub fn get_f32(&self, key: &str) -> Result<f32> {
+        let key = Map::make_raw_key(key)?;
+        unsafe { self.get_f32_raw_unchecked(&key, 0) }
+    }
+
     /// Retrieves an array of floating point numbers from a map.
     ///
     /// This is faster than iterating over a `get_float_iter()`.
@@ -484,6 +627,32 @@ impl<'elem> Map<'elem> {
         unsafe { ValueIter::<&[u8]>::new(self, key) }
     }
 
+    /// Retrieves a UTF-8 string from a map.
+    ///
+    /// This function retrieves the first value associated with the key. Any data which is valid
+    /// UTF-8 is accepted, regardless of its type hint.
+    #[inline]
+    pub fn get_str(&self, key: &str) -> Result<&str> {
+        let key = Map::make_raw_key(key)?;
+        unsafe { self.get_str_raw_unchecked(&key, 0) }
+    }
+
+    /// Retrieves UTF-8 strings from a map.
+    #[inline]
+    pub fn get_str_iter<'map>(&'map self, key: &str) -> Result<ValueIter<'map, 'elem, &'map str>> {
+        let key = Map::make_raw_key(key)?;
+        unsafe { ValueIter::<&str>::new(self, key) }
+    }
+
+    /// Retrieves the type hint of data from a map.
+    ///
+    /// This function retrieves the hint of the first value associated with the key.
+    #[inline]
+    pub fn data_type_hint(&self, key: &str) -> Result<DataTypeHint> {
+        let key = Map::make_raw_key(key)?;
+        unsafe { self.data_type_hint_raw_unchecked(&key, 0) }
+    }
+
     /// Retrieves a node from a map.
     ///
     /// This function retrieves the first value associated with the key.
@@ -555,6 +724,21 @@ impl<'elem> Map<'elem> {
         Ok(value)
     }
 
+    /// Retrieves an integer from a map, saturated to the `i32` range.
+    ///
+    /// # Safety
+    /// The caller must ensure `key` is valid.
+    #[inline]
+    pub(crate) unsafe fn get_i32_raw_unchecked(&self, key: &CStr, index: i32) -> Result<i32> {
+        let mut error = 0;
+        let value = unsafe {
+            API::get_cached().prop_get_int_saturated(self, key.as_ptr(), index, &mut error)
+        };
+        handle_get_prop_error(error)?;
+
+        Ok(value)
+    }
+
     /// Retrieves an array of integers from a map.
     ///
     /// # Safety
@@ -567,6 +751,10 @@ impl<'elem> Map<'elem> {
 
         unsafe {
             let length = self.value_count_raw_unchecked(key).unwrap();
+            if length == 0 {
+                // The pointer may be null for empty arrays.
+                return Ok(&[]);
+            }
             Ok(slice::from_raw_parts(value, length))
         }
     }
@@ -585,6 +773,21 @@ impl<'elem> Map<'elem> {
         Ok(value)
     }
 
+    /// Retrieves a floating point number from a map, saturated to the `f32` range.
+    ///
+    /// # Safety
+    /// The caller must ensure `key` is valid.
+    #[inline]
+    pub(crate) unsafe fn get_f32_raw_unchecked(&self, key: &CStr, index: i32) -> Result<f32> {
+        let mut error = 0;
+        let value = unsafe {
+            API::get_cached().prop_get_float_saturated(self, key.as_ptr(), index, &mut error)
+        };
+        handle_get_prop_error(error)?;
+
+        Ok(value)
+    }
+
     /// Retrieves an array of floating point numbers from a map.
     ///
     /// # Safety
@@ -598,6 +801,10 @@ impl<'elem> Map<'elem> {
 
         unsafe {
             let length = self.value_count_raw_unchecked(key).unwrap();
+            if length == 0 {
+                // The pointer may be null for empty arrays.
+                return Ok(&[]);
+            }
             Ok(slice::from_raw_parts(value, length))
         }
     }
@@ -622,6 +829,48 @@ impl<'elem> Map<'elem> {
         unsafe { Ok(slice::from_raw_parts(value as *const u8, length as usize)) }
     }
 
+    /// Retrieves a UTF-8 string from a map.
+    ///
+    /// # Safety
+    /// The caller must ensure `key` is valid.
+    #[inline]
+    pub(crate) unsafe fn get_str_raw_unchecked(&self, key: &CStr, index: i32) -> Result<&str> {
+        let data = unsafe { self.get_data_raw_unchecked(key, index) }?;
+        Ok(str::from_utf8(data)?)
+    }
+
+    /// Retrieves a UTF-8 string from a map and copies it into a `String`.
+    ///
+    /// # Safety
+    /// The caller must ensure `key` is valid.
+    #[inline]
+    pub(crate) unsafe fn get_string_raw_unchecked(&self, key: &CStr, index: i32) -> Result<String> {
+        unsafe { self.get_str_raw_unchecked(key, index) }.map(str::to_owned)
+    }
+
+    /// Retrieves the type hint of data from a map.
+    ///
+    /// # Safety
+    /// The caller must ensure `key` is valid.
+    #[inline]
+    pub(crate) unsafe fn data_type_hint_raw_unchecked(
+        &self,
+        key: &CStr,
+        index: i32,
+    ) -> Result<DataTypeHint> {
+        let mut error = 0;
+        let hint = unsafe {
+            API::get_cached().prop_get_data_type_hint(self, key.as_ptr(), index, &mut error)
+        };
+        handle_get_prop_error(error)?;
+
+        match hint {
+            x if x == ffi::VSDataTypeHint_dtBinary => Ok(DataTypeHint::Binary),
+            x if x == ffi::VSDataTypeHint_dtUtf8 => Ok(DataTypeHint::Utf8),
+            _ => Ok(DataTypeHint::Unknown),
+        }
+    }
+
     /// Retrieves a node from a map.
     ///
     /// # Safety
@@ -697,6 +946,13 @@ impl<'elem> Map<'elem> {
         unsafe { self.append_data_raw_unchecked(&key, x) }
     }
 
+    /// Appends a UTF-8 string to a map.
+    #[inline]
+    pub fn append_str(&mut self, key: &str, x: &str) -> Result<()> {
+        let key = Map::make_raw_key(key)?;
+        unsafe { self.append_str_raw_unchecked(&key, x) }
+    }
+
     /// Appends a node to a map.
     #[inline]
     pub fn append_node(&mut self, key: &str, x: &Node<'elem>) -> Result<()> {
@@ -718,6 +974,60 @@ impl<'elem> Map<'elem> {
         unsafe { self.append_function_raw_unchecked(&key, x) }
     }
 
+    /// Appends a node to a map, moving the reference into the map.
+    ///
+    /// Unlike `append_node()`, this doesn't need to increase the reference count.
+    #[inline]
+    pub fn append_node_owned(&mut self, key: &str, x: Node<'elem>) -> Result<()> {
+        let key = Map::make_raw_key(key)?;
+        let error = unsafe {
+            API::get_cached().prop_consume_node(
+                self,
+                key.as_ptr(),
+                x.into_ptr(),
+                ffi::VSMapAppendMode_maAppend,
+            )
+        };
+
+        handle_append_prop_error(error)
+    }
+
+    /// Appends a frame to a map, moving the reference into the map.
+    ///
+    /// Unlike `append_frame()`, this doesn't need to increase the reference count.
+    #[inline]
+    pub fn append_frame_owned(&mut self, key: &str, x: FrameRef<'elem>) -> Result<()> {
+        let key = Map::make_raw_key(key)?;
+        let error = unsafe {
+            API::get_cached().prop_consume_frame(
+                self,
+                key.as_ptr(),
+                x.into_ptr(),
+                ffi::VSMapAppendMode_maAppend,
+            )
+        };
+
+        handle_append_prop_error(error)
+    }
+
+    /// Appends a function to a map, moving the reference into the map.
+    ///
+    /// Unlike `append_function()`, this doesn't need to increase the reference count.
+    #[inline]
+    pub fn append_function_owned(&mut self, key: &str, x: Function<'elem>) -> Result<()> {
+        let key = Map::make_raw_key(key)?;
+        let error = unsafe {
+            API::get_cached().prop_consume_func(
+                self,
+                key.as_ptr(),
+                x.into_ptr(),
+                ffi::VSMapAppendMode_maAppend,
+            )
+        };
+
+        handle_append_prop_error(error)
+    }
+
     /// Appends an integer to a map.
     ///
     /// # Safety
@@ -751,7 +1061,32 @@ impl<'elem> Map<'elem> {
     #[inline]
     pub(crate) unsafe fn append_data_raw_unchecked(&mut self, key: &CStr, x: &[u8]) -> Result<()> {
         let error = unsafe {
-            API::get_cached().prop_set_data(self, key.as_ptr(), x, ffi::VSMapAppendMode_maAppend)
+            API::get_cached().prop_set_data(
+                self,
+                key.as_ptr(),
+                x,
+                ffi::VSDataTypeHint_dtUnknown,
+                ffi::VSMapAppendMode_maAppend,
+            )
+        };
+
+        handle_append_prop_error(error)
+    }
+
+    /// Appends a UTF-8 string to a map.
+    ///
+    /// # Safety
+    /// The caller must ensure `key` is valid.
+    #[inline]
+    pub(crate) unsafe fn append_str_raw_unchecked(&mut self, key: &CStr, x: &str) -> Result<()> {
+        let error = unsafe {
+            API::get_cached().prop_set_data(
+                self,
+                key.as_ptr(),
+                x.as_bytes(),
+                ffi::VSDataTypeHint_dtUtf8,
+                ffi::VSMapAppendMode_maAppend,
+            )
         };
 
         handle_append_prop_error(error)
@@ -823,6 +1158,20 @@ impl<'elem> Map<'elem> {
         handle_append_prop_error(error)
     }
 
+    /// Sets a property value to an empty array of the given type.
+    ///
+    /// Any existing values of the key are removed.
+    #[inline]
+    pub fn set_empty(&mut self, key: &str, value_type: ValueType) -> Result<()> {
+        let key = Map::make_raw_key(key)?;
+        unsafe {
+            API::get_cached().prop_delete_key(self, key.as_ptr());
+            let error = API::get_cached().prop_set_empty(self, key.as_ptr(), value_type.ffi_type());
+            debug_assert!(error == 0);
+        }
+        Ok(())
+    }
+
     /// Sets a property value to an integer.
     #[inline]
     pub fn set_int(&mut self, key: &str, x: i64) -> Result<()> {
@@ -877,6 +1226,16 @@ impl<'elem> Map<'elem> {
         Ok(())
     }
 
+    /// Sets a property value to a UTF-8 string.
+    #[inline]
+    pub fn set_str(&mut self, key: &str, x: &str) -> Result<()> {
+        let key = Map::make_raw_key(key)?;
+        unsafe {
+            self.set_str_raw_unchecked(&key, x);
+        }
+        Ok(())
+    }
+
     /// Sets a property value to a node.
     #[inline]
     pub fn set_node(&mut self, key: &str, x: &Node<'elem>) -> Result<()> {
@@ -907,6 +1266,63 @@ impl<'elem> Map<'elem> {
         Ok(())
     }
 
+    /// Sets a property value to a node, moving the reference into the map.
+    ///
+    /// Unlike `set_node()`, this doesn't need to increase the reference count.
+    #[inline]
+    pub fn set_node_owned(&mut self, key: &str, x: Node<'elem>) -> Result<()> {
+        let key = Map::make_raw_key(key)?;
+        let error = unsafe {
+            API::get_cached().prop_consume_node(
+                self,
+                key.as_ptr(),
+                x.into_ptr(),
+                ffi::VSMapAppendMode_maReplace,
+            )
+        };
+
+        debug_assert!(error == 0);
+        Ok(())
+    }
+
+    /// Sets a property value to a frame, moving the reference into the map.
+    ///
+    /// Unlike `set_frame()`, this doesn't need to increase the reference count.
+    #[inline]
+    pub fn set_frame_owned(&mut self, key: &str, x: FrameRef<'elem>) -> Result<()> {
+        let key = Map::make_raw_key(key)?;
+        let error = unsafe {
+            API::get_cached().prop_consume_frame(
+                self,
+                key.as_ptr(),
+                x.into_ptr(),
+                ffi::VSMapAppendMode_maReplace,
+            )
+        };
+
+        debug_assert!(error == 0);
+        Ok(())
+    }
+
+    /// Sets a property value to a function, moving the reference into the map.
+    ///
+    /// Unlike `set_function()`, this doesn't need to increase the reference count.
+    #[inline]
+    pub fn set_function_owned(&mut self, key: &str, x: Function<'elem>) -> Result<()> {
+        let key = Map::make_raw_key(key)?;
+        let error = unsafe {
+            API::get_cached().prop_consume_func(
+                self,
+                key.as_ptr(),
+                x.into_ptr(),
+                ffi::VSMapAppendMode_maReplace,
+            )
+        };
+
+        debug_assert!(error == 0);
+        Ok(())
+    }
+
     /// Sets a property value to an integer.
     ///
     /// # Safety
@@ -968,7 +1384,32 @@ impl<'elem> Map<'elem> {
     #[inline]
     pub(crate) unsafe fn set_data_raw_unchecked(&mut self, key: &CStr, x: &[u8]) {
         let error = unsafe {
-            API::get_cached().prop_set_data(self, key.as_ptr(), x, ffi::VSMapAppendMode_maReplace)
+            API::get_cached().prop_set_data(
+                self,
+                key.as_ptr(),
+                x,
+                ffi::VSDataTypeHint_dtUnknown,
+                ffi::VSMapAppendMode_maReplace,
+            )
+        };
+
+        debug_assert!(error == 0);
+    }
+
+    /// Sets a property value to a UTF-8 string.
+    ///
+    /// # Safety
+    /// The caller must ensure `key` is valid.
+    #[inline]
+    pub(crate) unsafe fn set_str_raw_unchecked(&mut self, key: &CStr, x: &str) {
+        let error = unsafe {
+            API::get_cached().prop_set_data(
+                self,
+                key.as_ptr(),
+                x.as_bytes(),
+                ffi::VSDataTypeHint_dtUtf8,
+                ffi::VSMapAppendMode_maReplace,
+            )
         };
 
         debug_assert!(error == 0);
@@ -0,0 +1,397 @@
+//! Serde support for maps.
+//!
+//! A key with a single value is represented as that value, and a key with any other number of
+//! values is represented as an array. Integers, floating point numbers, UTF-8 strings and binary
+//! data are supported; nodes, frames and functions can't be serialized.
+//!
+//! Binary data is serialized with `serialize_bytes()`, so its representation depends on the
+//! format. Formats without a byte string type, like JSON, store it as an array of integers, which
+//! is deserialized back as integers: binary data doesn't round-trip through such formats.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use ::serde::de::value::{BorrowedBytesDeserializer, BorrowedStrDeserializer, SeqDeserializer};
+use ::serde::de::{
+    self, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use ::serde::ser::{self, SerializeMap, Serializer};
+use ::serde::{Deserialize, Serialize, forward_to_deserialize_any};
+use thiserror::Error;
+
+use super::*;
+
+/// The error type for deserializing a value from a `Map`.
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+#[error("{0}")]
+pub struct DeserializeError(String);
+
+impl de::Error for DeserializeError {
+    #[inline]
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeserializeError(msg.to_string())
+    }
+}
+
+/// Deserializes a value, for example a user struct, from a map.
+///
+/// Keys correspond to struct fields. A key with a single value can be deserialized both into a
+/// scalar and into a sequence, a key with any other number of values only into a sequence.
+#[inline]
+pub fn from_map<'de, T: Deserialize<'de>>(
+    map: &'de Map<'_>,
+) -> result::Result<T, DeserializeError> {
+    T::deserialize(MapDeserializer { map })
+}
+
+/// Binary data, serialized with `serialize_bytes()`.
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// Serializes a single value as itself and any other number of values as a sequence.
+fn serialize_values<S: Serializer, T: Serialize>(
+    values: &[T],
+    serializer: S,
+) -> result::Result<S::Ok, S::Error> {
+    if let [value] = values {
+        value.serialize(serializer)
+    } else {
+        values.serialize(serializer)
+    }
+}
+
+impl Serialize for MapValue<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        match self {
+            MapValue::Int(x) => serialize_values(x, serializer),
+            MapValue::Float(x) => serialize_values(x, serializer),
+            MapValue::Str(x) => serialize_values(x, serializer),
+            MapValue::Data(x) => {
+                let x = x.iter().map(|x| Bytes(x)).collect::<Vec<_>>();
+                serialize_values(&x, serializer)
+            }
+            _ => Err(ser::Error::custom(format_args!(
+                "{:?} values can't be serialized",
+                self.value_type()
+            ))),
+        }
+    }
+}
+
+impl Serialize for Map<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.key_count()))?;
+        for (key, value) in self.iter() {
+            map.serialize_entry(key, &value)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for OwnedMap<'_> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        self.map.serialize(serializer)
+    }
+}
+
+impl Serialize for MapRef<'_, '_> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        self.map.serialize(serializer)
+    }
+}
+
+impl Serialize for MapRefMut<'_, '_> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        self.map.serialize(serializer)
+    }
+}
+
+/// Deserializes a map from a serde map.
+///
+/// Booleans are stored as integers. Empty arrays are skipped since their value type is unknown.
+impl<'de> Deserialize<'de> for OwnedMap<'_> {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+        deserializer.deserialize_map(OwnedMapVisitor(PhantomData))
+    }
+}
+
+struct OwnedMapVisitor<'elem>(PhantomData<&'elem ()>);
+
+impl<'de, 'elem> Visitor<'de> for OwnedMapVisitor<'elem> {
+    type Value = OwnedMap<'elem>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> result::Result<Self::Value, A::Error> {
+        let api =
+            API::get().ok_or_else(|| de::Error::custom("couldn't get the VapourSynth API"))?;
+        let mut map = OwnedMap::new(api);
+
+        while let Some(key) = access.next_key::<String>()? {
+            Map::is_key_valid(&key).map_err(de::Error::custom)?;
+
+            // Later values of duplicate keys replace the earlier ones.
+            let _ = map.delete_key(&key);
+
+            access.next_value_seed(ValuesSeed {
+                map: &mut map,
+                key: &key,
+                nested: false,
+            })?;
+        }
+
+        Ok(map)
+    }
+}
+
+/// Appends the deserialized value or values to the key.
+struct ValuesSeed<'a, 'elem> {
+    map: &'a mut Map<'elem>,
+    key: &'a str,
+    // Whether this is an element of an array.
+    nested: bool,
+}
+
+impl ValuesSeed<'_, '_> {
+    #[inline]
+    fn append<E: de::Error>(&mut self, rv: Result<()>) -> result::Result<(), E> {
+        rv.map_err(|e| match e {
+            Error::WrongValueType => {
+                de::Error::custom(format_args!("mixed value types in key {}", self.key))
+            }
+            e => de::Error::custom(e),
+        })
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for ValuesSeed<'_, '_> {
+    type Value = ();
+
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> result::Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValuesSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.nested {
+            formatter.write_str("an integer, a floating point number, a string or bytes")
+        } else {
+            formatter.write_str("an integer, a floating point number, a string, bytes or an array")
+        }
+    }
+
+    #[inline]
+    fn visit_bool<E: de::Error>(self, v: bool) -> result::Result<(), E> {
+        self.visit_i64(i64::from(v))
+    }
+
+    #[inline]
+    fn visit_i64<E: de::Error>(mut self, v: i64) -> result::Result<(), E> {
+        let rv = self.map.append_int(self.key, v);
+        self.append(rv)
+    }
+
+    #[inline]
+    fn visit_u64<E: de::Error>(self, v: u64) -> result::Result<(), E> {
+        let v = i64::try_from(v)
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Unsigned(v), &"an i64"))?;
+        self.visit_i64(v)
+    }
+
+    #[inline]
+    fn visit_f64<E: de::Error>(mut self, v: f64) -> result::Result<(), E> {
+        let rv = self.map.append_float(self.key, v);
+        self.append(rv)
+    }
+
+    #[inline]
+    fn visit_str<E: de::Error>(mut self, v: &str) -> result::Result<(), E> {
+        let rv = self.map.append_str(self.key, v);
+        self.append(rv)
+    }
+
+    #[inline]
+    fn visit_bytes<E: de::Error>(mut self, v: &[u8]) -> result::Result<(), E> {
+        let rv = self.map.append_data(self.key, v);
+        self.append(rv)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> result::Result<(), A::Error> {
+        if self.nested {
+            return Err(de::Error::invalid_type(de::Unexpected::Seq, &self));
+        }
+
+        while seq
+            .next_element_seed(ValuesSeed {
+                map: &mut *self.map,
+                key: self.key,
+                nested: true,
+            })?
+            .is_some()
+        {}
+
+        Ok(())
+    }
+}
+
+/// A deserializer over the entries of a map.
+struct MapDeserializer<'de, 'elem> {
+    map: &'de Map<'elem>,
+}
+
+impl<'de> Deserializer<'de> for MapDeserializer<'de, '_> {
+    type Error = DeserializeError;
+
+    #[inline]
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> result::Result<V::Value, Self::Error> {
+        visitor.visit_map(Entries {
+            iter: self.map.iter(),
+            value: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Access to the entries of a map.
+struct Entries<'de, 'elem> {
+    iter: Iter<'de, 'elem>,
+    value: Option<MapValue<'de, 'elem>>,
+}
+
+impl<'de, 'elem> MapAccess<'de> for Entries<'de, 'elem> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> result::Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> result::Result<V::Value, Self::Error> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed() called before next_key_seed()");
+        seed.deserialize(ValueDeserializer(value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// A deserializer over the values of a key.
+struct ValueDeserializer<'de, 'elem>(MapValue<'de, 'elem>);
+
+impl ValueDeserializer<'_, '_> {
+    #[inline]
+    fn unsupported(&self) -> DeserializeError {
+        de::Error::custom(format_args!(
+            "{:?} values can't be deserialized",
+            self.0.value_type()
+        ))
+    }
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de, '_> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> result::Result<V::Value, Self::Error> {
+        match self.0 {
+            MapValue::Int([x]) => visitor.visit_i64(*x),
+            MapValue::Float([x]) => visitor.visit_f64(*x),
+            MapValue::Str(ref x) if x.len() == 1 => visitor.visit_borrowed_str(x[0]),
+            MapValue::Data(ref x) if x.len() == 1 => visitor.visit_borrowed_bytes(x[0]),
+            _ => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> result::Result<V::Value, Self::Error> {
+        match self.0 {
+            MapValue::Int(x) => visitor.visit_seq(SeqDeserializer::new(
+                x.iter().map(|x| x.into_deserializer()),
+            )),
+            MapValue::Float(x) => visitor.visit_seq(SeqDeserializer::new(
+                x.iter().map(|x| x.into_deserializer()),
+            )),
+            MapValue::Str(x) => visitor.visit_seq(SeqDeserializer::new(
+                x.into_iter().map(BorrowedStrDeserializer::new),
+            )),
+            MapValue::Data(x) => visitor.visit_seq(SeqDeserializer::new(
+                x.into_iter().map(BorrowedBytesDeserializer::new),
+            )),
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    #[inline]
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> result::Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> result::Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> result::Result<V::Value, Self::Error> {
+        // Unit variants are stored as strings.
+        match self.0 {
+            MapValue::Str(ref x) if x.len() == 1 => {
+                visitor.visit_enum(BorrowedStrDeserializer::new(x[0]))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
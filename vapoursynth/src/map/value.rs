@@ -1,3 +1,5 @@
+use vapoursynth_sys as ffi;
+
 use crate::frame::FrameRef;
 use crate::function::Function;
 use crate::map::{Map, Result, ValueIter};
@@ -16,8 +18,117 @@ pub enum ValueType {
     Function,
 }
 
+impl ValueType {
+    /// Returns the corresponding `VSPropertyType`.
+    #[inline]
+    pub(crate) fn ffi_type(self) -> ffi::VSPropertyType {
+        match self {
+            ValueType::Int => ffi::VSPropertyType_ptInt,
+            ValueType::Float => ffi::VSPropertyType_ptFloat,
+            ValueType::Data => ffi::VSPropertyType_ptData,
+            ValueType::VideoNode => ffi::VSPropertyType_ptVideoNode,
+            ValueType::AudioNode => ffi::VSPropertyType_ptAudioNode,
+            ValueType::VideoFrame => ffi::VSPropertyType_ptVideoFrame,
+            ValueType::AudioFrame => ffi::VSPropertyType_ptAudioFrame,
+            ValueType::Function => ffi::VSPropertyType_ptFunction,
+        }
+    }
+}
+
+/// A hint of what kind of contents a data value has.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DataTypeHint {
+    /// The contents are unknown, this is the case for data set without a hint.
+    Unknown,
+    /// Binary data, exposed as `bytes` in Python.
+    Binary,
+    /// A UTF-8 string, exposed as `str` in Python.
+    Utf8,
+}
+
+/// All values associated with a key of a map, whatever their type.
+#[derive(Debug, Clone)]
+pub enum MapValue<'map, 'elem: 'map> {
+    Int(&'map [i64]),
+    Float(&'map [f64]),
+    /// Data which isn't marked as UTF-8.
+    Data(Vec<&'map [u8]>),
+    /// Data which is marked as UTF-8.
+    Str(Vec<&'map str>),
+    VideoNode(Vec<Node<'elem>>),
+    /// Audio isn't supported, so only the number of audio nodes is provided.
+    AudioNode(usize),
+    VideoFrame(Vec<FrameRef<'elem>>),
+    /// Audio isn't supported, so only the number of audio frames is provided.
+    AudioFrame(usize),
+    Function(Vec<Function<'elem>>),
+}
+
+impl<'map, 'elem> MapValue<'map, 'elem> {
+    /// Returns the type of the values.
+    #[inline]
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            MapValue::Int(_) => ValueType::Int,
+            MapValue::Float(_) => ValueType::Float,
+            MapValue::Data(_) | MapValue::Str(_) => ValueType::Data,
+            MapValue::VideoNode(_) => ValueType::VideoNode,
+            MapValue::AudioNode(_) => ValueType::AudioNode,
+            MapValue::VideoFrame(_) => ValueType::VideoFrame,
+            MapValue::AudioFrame(_) => ValueType::AudioFrame,
+            MapValue::Function(_) => ValueType::Function,
+        }
+    }
+
+    /// Returns the number of values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            MapValue::Int(x) => x.len(),
+            MapValue::Float(x) => x.len(),
+            MapValue::Data(x) => x.len(),
+            MapValue::Str(x) => x.len(),
+            MapValue::VideoNode(x) => x.len(),
+            MapValue::AudioNode(x) => *x,
+            MapValue::VideoFrame(x) => x.len(),
+            MapValue::AudioFrame(x) => *x,
+            MapValue::Function(x) => x.len(),
+        }
+    }
+
+    /// Returns `true` if there are no values.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Nodes, frames and functions are compared by identity.
+impl<'map, 'elem> PartialEq for MapValue<'map, 'elem> {
+    fn eq(&self, other: &Self) -> bool {
+        fn same<T, U>(a: &[T], b: &[T], ptr: impl Fn(&T) -> *const U) -> bool {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| ptr(a) == ptr(b))
+        }
+
+        match (self, other) {
+            (MapValue::Int(a), MapValue::Int(b)) => a == b,
+            (MapValue::Float(a), MapValue::Float(b)) => a == b,
+            (MapValue::Data(a), MapValue::Data(b)) => a == b,
+            (MapValue::Str(a), MapValue::Str(b)) => a == b,
+            (MapValue::VideoNode(a), MapValue::VideoNode(b)) => same(a, b, |x| x.ptr() as *const _),
+            (MapValue::AudioNode(a), MapValue::AudioNode(b)) => a == b,
+            (MapValue::VideoFrame(a), MapValue::VideoFrame(b)) => {
+                same(a, b, |x| &***x as *const ffi::VSFrame)
+            }
+            (MapValue::AudioFrame(a), MapValue::AudioFrame(b)) => a == b,
+            (MapValue::Function(a), MapValue::Function(b)) => same(a, b, |x| x.ptr() as *const _),
+            _ => false,
+        }
+    }
+}
+
 /// A trait for values which can be stored in a map.
-pub trait Value<'map, 'elem: 'map>: Sized {
+pub trait Value<'map, 'elem>: Sized {
     /// Retrieves the value from the map.
     fn get_from_map(map: &'map Map<'elem>, key: &str) -> Result<Self>;
 
@@ -31,7 +142,7 @@ pub trait Value<'map, 'elem: 'map>: Sized {
     fn append_to_map(map: &'map mut Map<'elem>, key: &str, x: &Self) -> Result<()>;
 }
 
-impl<'map, 'elem: 'map> Value<'map, 'elem> for i64 {
+impl<'map, 'elem> Value<'map, 'elem> for i64 {
     #[inline]
     fn get_from_map(map: &Map, key: &str) -> Result<Self> {
         map.get_int(key)
@@ -53,7 +164,7 @@ impl<'map, 'elem: 'map> Value<'map, 'elem> for i64 {
     }
 }
 
-impl<'map, 'elem: 'map> Value<'map, 'elem> for f64 {
+impl<'map, 'elem> Value<'map, 'elem> for f64 {
     fn get_from_map(map: &Map, key: &str) -> Result<Self> {
         map.get_float(key)
     }
@@ -96,7 +207,52 @@ impl<'map, 'elem: 'map> Value<'map, 'elem> for &'map [u8] {
     }
 }
 
-impl<'map, 'elem: 'map> Value<'map, 'elem> for Node<'elem> {
+impl<'map, 'elem: 'map> Value<'map, 'elem> for &'map str {
+    #[inline]
+    fn get_from_map(map: &'map Map, key: &str) -> Result<Self> {
+        map.get_str(key)
+    }
+
+    #[inline]
+    fn get_iter_from_map(map: &'map Map<'elem>, key: &str) -> Result<ValueIter<'map, 'elem, Self>> {
+        map.get_str_iter(key)
+    }
+
+    #[inline]
+    fn store_in_map(map: &'map mut Map, key: &str, x: &Self) -> Result<()> {
+        map.set_str(key, x)
+    }
+
+    #[inline]
+    fn append_to_map(map: &'map mut Map, key: &str, x: &Self) -> Result<()> {
+        map.append_str(key, x)
+    }
+}
+
+impl<'map, 'elem> Value<'map, 'elem> for String {
+    #[inline]
+    fn get_from_map(map: &Map, key: &str) -> Result<Self> {
+        map.get_str(key).map(str::to_owned)
+    }
+
+    #[inline]
+    fn get_iter_from_map(map: &'map Map<'elem>, key: &str) -> Result<ValueIter<'map, 'elem, Self>> {
+        let key = Map::make_raw_key(key)?;
+        unsafe { ValueIter::<String>::new(map, key) }
+    }
+
+    #[inline]
+    fn store_in_map(map: &mut Map, key: &str, x: &Self) -> Result<()> {
+        map.set_str(key, x)
+    }
+
+    #[inline]
+    fn append_to_map(map: &mut Map, key: &str, x: &Self) -> Result<()> {
+        map.append_str(key, x)
+    }
+}
+
+impl<'map, 'elem> Value<'map, 'elem> for Node<'elem> {
     #[inline]
     fn get_from_map(map: &Map<'elem>, key: &str) -> Result<Self> {
         map.get_video_node(key)
@@ -118,7 +274,7 @@ impl<'map, 'elem: 'map> Value<'map, 'elem> for Node<'elem> {
     }
 }
 
-impl<'map, 'elem: 'map> Value<'map, 'elem> for FrameRef<'elem> {
+impl<'map, 'elem> Value<'map, 'elem> for FrameRef<'elem> {
     #[inline]
     fn get_from_map(map: &Map<'elem>, key: &str) -> Result<Self> {
         map.get_video_frame(key)
@@ -140,7 +296,7 @@ impl<'map, 'elem: 'map> Value<'map, 'elem> for FrameRef<'elem> {
     }
 }
 
-impl<'map, 'elem: 'map> Value<'map, 'elem> for Function<'elem> {
+impl<'map, 'elem> Value<'map, 'elem> for Function<'elem> {
     #[inline]
     fn get_from_map(map: &Map<'elem>, key: &str) -> Result<Self> {
         map.get_function(key)
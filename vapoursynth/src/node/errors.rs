@@ -28,6 +28,12 @@ impl<'a> GetFrameError<'a> {
         GetFrameError(message)
     }
 
+    /// Converts this error into one which owns the error message.
+    #[inline]
+    pub fn into_owned(self) -> GetFrameError<'static> {
+        GetFrameError(Cow::Owned(self.0.into_owned()))
+    }
+
     /// Consumes this error, returning its underlying error message.
     #[inline]
     pub fn into_inner(self) -> Cow<'a, CStr> {
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::iter::FusedIterator;
+use std::mem;
+use std::ops::Range;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::Waker;
+
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+
+use crate::frame::FrameRef;
+
+use super::{GetFrameError, Node};
+
+type FrameResult<'core> = Result<FrameRef<'core>, GetFrameError<'static>>;
+
+/// The state shared between `Frames` and the frame callbacks.
+struct Shared<'core> {
+    // Frames which are ready but haven't been returned yet, keyed by the frame number.
+    ready: Mutex<Ready<'core>>,
+    condvar: Condvar,
+}
+
+struct Ready<'core> {
+    frames: HashMap<usize, FrameResult<'core>>,
+    waker: Option<Waker>,
+    // Number of requested frames whose callbacks haven't finished yet.
+    pending: usize,
+}
+
+/// An iterator over a range of frames of a node, in order, created by `Node::frames()`.
+///
+/// Frames are generated concurrently, with at most `max_in_flight` frames requested or waiting to
+/// be returned at any time. The iteration stops after the first error.
+///
+/// Dropping the iterator cancels the iteration: no more frames are requested. Since the frames
+/// which are already being generated belong to the core, dropping blocks until they're ready, and
+/// then drops them.
+///
+/// With the `stream` feature, this also implements `futures_core::Stream`, which waits for
+/// frames asynchronously instead of blocking the thread.
+pub struct Frames<'core> {
+    node: Node<'core>,
+    shared: Arc<Shared<'core>>,
+    // The next frame to request.
+    next_request: usize,
+    // The next frame to return.
+    next_output: usize,
+    end: usize,
+    max_in_flight: usize,
+    failed: bool,
+}
+
+impl<'core> Frames<'core> {
+    /// Creates a new iterator over `range` of frames of `node`.
+    pub(crate) fn new(node: &Node<'core>, range: Range<usize>, max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "max_in_flight must be greater than zero");
+
+        Self {
+            node: node.clone(),
+            shared: Arc::new(Shared {
+                ready: Mutex::new(Ready {
+                    frames: HashMap::new(),
+                    waker: None,
+                    pending: 0,
+                }),
+                condvar: Condvar::new(),
+            }),
+            next_request: range.start,
+            next_output: range.start,
+            end: range.end.max(range.start),
+            max_in_flight,
+            failed: false,
+        }
+    }
+
+    /// Returns the maximal number of frames requested or waiting to be returned at a time.
+    #[inline]
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// Changes the maximal number of frames requested or waiting to be returned at a time.
+    ///
+    /// Lowering the limit doesn't cancel frames which were already requested: new frames won't be
+    /// requested until enough of them are returned.
+    ///
+    /// # Panics
+    /// Panics if `max_in_flight` is zero.
+    #[inline]
+    pub fn set_max_in_flight(&mut self, max_in_flight: usize) {
+        assert!(max_in_flight > 0, "max_in_flight must be greater than zero");
+        self.max_in_flight = max_in_flight;
+    }
+
+    /// Returns whether all frames have been returned or the iteration has failed.
+    #[inline]
+    fn is_finished(&self) -> bool {
+        self.failed || self.next_output == self.end
+    }
+
+    /// Requests frames until the request window is full.
+    fn request_frames(&mut self) {
+        while self.next_request < self.end
+            && self.next_request - self.next_output < self.max_in_flight
+        {
+            self.shared.ready.lock().unwrap().pending += 1;
+
+            let shared = self.shared.clone();
+            self.node
+                .get_frame_async(self.next_request, move |frame, n, node| {
+                    // Nothing belonging to the core may be dropped after the request is marked
+                    // as finished, since `Drop` doesn't wait any longer than that.
+                    drop(node);
+
+                    let waker = {
+                        let mut ready = shared.ready.lock().unwrap();
+                        ready
+                            .frames
+                            .insert(n, frame.map_err(GetFrameError::into_owned));
+                        ready.pending -= 1;
+                        ready.waker.take()
+                    };
+
+                    shared.condvar.notify_one();
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                });
+
+            self.next_request += 1;
+        }
+    }
+
+    /// Advances past a frame which was taken from the ready frames.
+    #[inline]
+    fn advance(&mut self, frame: FrameResult<'core>) -> FrameResult<'core> {
+        self.next_output += 1;
+        self.failed = frame.is_err();
+        frame
+    }
+}
+
+impl<'core> Iterator for Frames<'core> {
+    type Item = FrameResult<'core>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished() {
+            return None;
+        }
+
+        self.request_frames();
+
+        let frame = {
+            let mut ready = self.shared.ready.lock().unwrap();
+            loop {
+                if let Some(frame) = ready.frames.remove(&self.next_output) {
+                    break frame;
+                }
+
+                ready = self.shared.condvar.wait(ready).unwrap();
+            }
+        };
+
+        Some(self.advance(frame))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            (0, Some(0))
+        } else {
+            (0, Some(self.end - self.next_output))
+        }
+    }
+}
+
+impl<'core> FusedIterator for Frames<'core> {}
+
+impl<'core> Drop for Frames<'core> {
+    fn drop(&mut self) {
+        let frames = {
+            let mut ready = self.shared.ready.lock().unwrap();
+            while ready.pending > 0 {
+                ready = self.shared.condvar.wait(ready).unwrap();
+            }
+
+            mem::take(&mut ready.frames)
+        };
+
+        drop(frames);
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<'core> futures_core::Stream for Frames<'core> {
+    type Item = FrameResult<'core>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if self.is_finished() {
+            return Poll::Ready(None);
+        }
+
+        self.request_frames();
+
+        let frame = {
+            let mut ready = self.shared.ready.lock().unwrap();
+            match ready.frames.remove(&self.next_output) {
+                Some(frame) => frame,
+                None => {
+                    ready.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        };
+
+        Poll::Ready(Some(self.advance(frame)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        Iterator::size_hint(self)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<'core> futures_core::FusedStream for Frames<'core> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        self.is_finished()
+    }
+}
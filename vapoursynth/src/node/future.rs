@@ -0,0 +1,100 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::frame::FrameRef;
+
+use super::{GetFrameError, Node};
+
+/// The state shared between a `FrameFuture` and its callback.
+struct Shared<'core> {
+    state: Mutex<State<'core>>,
+    condvar: Condvar,
+}
+
+struct State<'core> {
+    result: Option<Result<FrameRef<'core>, GetFrameError<'static>>>,
+    waker: Option<Waker>,
+    // Whether the callback has finished.
+    done: bool,
+}
+
+/// A future resolving to a frame requested with `Node::get_frame_future()`.
+///
+/// The frame is requested when the future is created. Dropping the future doesn't cancel the
+/// request: since the frame belongs to the core, dropping blocks until it's ready, and then drops
+/// it.
+#[must_use = "futures do nothing unless polled, and the frame is dropped if the future is dropped"]
+pub struct FrameFuture<'core> {
+    shared: Arc<Shared<'core>>,
+}
+
+impl<'core> FrameFuture<'core> {
+    /// Requests frame `n` from `node`.
+    pub(crate) fn new(node: &Node<'core>, n: usize) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                result: None,
+                waker: None,
+                done: false,
+            }),
+            condvar: Condvar::new(),
+        });
+
+        let callback_shared = shared.clone();
+        node.get_frame_async(n, move |frame, _n, node| {
+            // Nothing belonging to the core may be dropped after the request is marked as done,
+            // since `Drop` doesn't wait any longer than that.
+            drop(node);
+
+            let waker = {
+                let mut state = callback_shared.state.lock().unwrap();
+                state.result = Some(frame.map_err(GetFrameError::into_owned));
+                state.done = true;
+                state.waker.take()
+            };
+
+            callback_shared.condvar.notify_one();
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        });
+
+        Self { shared }
+    }
+}
+
+impl<'core> Future for FrameFuture<'core> {
+    type Output = Result<FrameRef<'core>, GetFrameError<'static>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                match state.waker {
+                    Some(ref waker) if waker.will_wake(cx.waker()) => {}
+                    _ => state.waker = Some(cx.waker().clone()),
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<'core> Drop for FrameFuture<'core> {
+    fn drop(&mut self) {
+        let result = {
+            let mut state = self.shared.state.lock().unwrap();
+            while !state.done {
+                state = self.shared.condvar.wait(state).unwrap();
+            }
+
+            state.result.take()
+        };
+
+        drop(result);
+    }
+}
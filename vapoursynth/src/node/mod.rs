@@ -3,6 +3,7 @@
 use std::borrow::Cow;
 use std::ffi::{CStr, CString};
 use std::marker::PhantomData;
+use std::ops::Range;
 use std::os::raw::{c_char, c_void};
 use std::process;
 use std::ptr::NonNull;
@@ -17,6 +18,12 @@ use crate::video_info::VideoInfo;
 mod errors;
 pub use self::errors::GetFrameError;
 
+mod frames;
+pub use self::frames::Frames;
+
+mod future;
+pub use self::future::FrameFuture;
+
 /// A reference to a node in the constructed filter graph.
 #[derive(Debug)]
 pub struct Node<'core> {
@@ -66,6 +73,14 @@ impl<'core> Node<'core> {
         self.handle.as_ptr()
     }
 
+    /// Returns the underlying pointer, transferring the reference to the caller.
+    #[inline]
+    pub(crate) fn into_ptr(self) -> *mut ffi::VSNode {
+        let ptr = self.ptr();
+        mem::forget(self);
+        ptr
+    }
+
     /// Returns the video info associated with this `Node`.
     // Since we don't store the pointer to the actual `ffi::VSVideoInfo` and the lifetime is that
     // of the `ffi::VSFormat`, this returns `VideoInfo<'core>` rather than `VideoInfo<'a>`.
@@ -123,6 +138,9 @@ impl<'core> Node<'core> {
     ///
     /// If the callback panics, the process is aborted.
     ///
+    /// Nothing waits for the callback to be called. Use `CoreRef::scope()` to make sure all
+    /// requests have finished before continuing, for example before dropping the core.
+    ///
     /// # Panics
     /// Panics is `n` is greater than `i32::MAX`.
     pub fn get_frame_async<F>(&self, n: usize, callback: F)
@@ -214,6 +232,59 @@ impl<'core> Node<'core> {
         mem::forget(new_node);
     }
 
+    /// Requests the generation of a frame, returning a future which resolves to it.
+    ///
+    /// The future doesn't depend on any particular executor: it's woken up from the VapourSynth
+    /// thread which generated the frame. The frame is requested immediately, even if the future
+    /// isn't polled. Dropping the future waits for the requested frame.
+    ///
+    /// # Panics
+    /// Panics is `n` is greater than `i32::MAX`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use vapoursynth::prelude::*;
+    /// # use vapoursynth::node::GetFrameError;
+    /// async fn frame_width(node: &Node<'_>, n: usize) -> Result<usize, GetFrameError<'static>> {
+    ///     let frame = node.get_frame_future(n).await?;
+    ///     Ok(frame.width(0))
+    /// }
+    /// ```
+    #[inline]
+    pub fn get_frame_future(&self, n: usize) -> FrameFuture<'core> {
+        FrameFuture::new(self, n)
+    }
+
+    /// Returns an iterator over the frames in `range`, in order.
+    ///
+    /// Up to `max_in_flight` frames are generated concurrently. Frames which are ready out of
+    /// order are held until all frames before them have been returned, and no new frames are
+    /// requested while the buffer is full, so at most `max_in_flight` frames are kept alive at a
+    /// time.
+    ///
+    /// The iterator stops after returning the first error. Dropping the iterator stops requesting
+    /// new frames and waits for the frames which were already requested.
+    ///
+    /// # Panics
+    /// Panics if `max_in_flight` is zero or if any frame number is greater than `i32::MAX`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use vapoursynth::prelude::*;
+    /// # use vapoursynth::node::GetFrameError;
+    /// # fn foo(node: &Node) -> Result<(), GetFrameError<'static>> {
+    /// for frame in node.frames(0..node.info().num_frames, 8) {
+    ///     let frame = frame?;
+    ///     // Do something with the frame.
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn frames(&self, range: Range<usize>, max_in_flight: usize) -> Frames<'core> {
+        Frames::new(self, range, max_in_flight)
+    }
+
     /// Requests a frame from a node and returns immediately.
     ///
     /// This is only used in filters' "get frame" functions.
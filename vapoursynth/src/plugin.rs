@@ -8,7 +8,7 @@ use vapoursynth_sys as ffi;
 
 use crate::api::API;
 use crate::map::{Map, OwnedMap};
-use crate::plugins::{self, FilterFunction};
+use crate::plugins::{self, FilterFunction, Version};
 
 /// A VapourSynth plugin.
 #[derive(Debug, Clone, Copy)]
@@ -47,6 +47,13 @@ impl<'core> Plugin<'core> {
         }
     }
 
+    /// Returns the version of the plugin.
+    #[inline]
+    pub fn version(&self) -> Version {
+        let version = unsafe { API::get_cached().get_plugin_version(self.handle.as_ptr()) };
+        Version::from_ffi_type(version)
+    }
+
     /// Invokes a filter.
     ///
     /// `invoke()` makes sure the filter has no compat input nodes, checks that the args passed to
@@ -79,7 +86,7 @@ impl<'core> Plugin<'core> {
         // TODO: this is almost the same code as plugins::ffi::call_register_function().
         let name_cstring = CString::new(filter_function.name())?;
         let args_cstring = CString::new(filter_function.args())?;
-        let return_type_cstring = CString::new("clip:vnode;")?;
+        let return_type_cstring = CString::new(filter_function.return_type())?;
 
         let data = Box::new(plugins::ffi::FilterFunctionData::<F> {
             filter_function,
@@ -0,0 +1,120 @@
+//! Structured filter arguments.
+
+use std::fmt::Debug;
+
+use thiserror::Error;
+
+use crate::map::{self, Map};
+
+use super::{FilterParameter, contains_key};
+
+/// The error type for parsing filter arguments.
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum ArgumentError {
+    #[error("Argument {key} is required")]
+    Missing { key: &'static str },
+    #[error("Couldn't get argument {key}")]
+    Map {
+        key: &'static str,
+        #[source]
+        source: map::Error,
+    },
+    #[error("Argument {key} is out of range: {value} is not in {range}")]
+    OutOfRange {
+        key: &'static str,
+        value: String,
+        range: &'static str,
+    },
+    #[error("Argument {key} doesn't fit into {target}: {value}")]
+    Overflow {
+        key: &'static str,
+        value: i64,
+        target: &'static str,
+    },
+    #[error("Argument {key} has an invalid value: {value}")]
+    InvalidValue { key: &'static str, value: String },
+}
+
+/// A structure holding the arguments of a filter function.
+///
+/// With the `macros` feature enabled, this trait can be derived for structs with named fields.
+/// Each field corresponds to an argument named after the field. Field types must implement
+/// `FilterParameter`, like the parameters of `make_filter_function!`: fields of type `Option<T>`
+/// are optional, fields of type `Vec<T>` are arrays which can be empty. The following field
+/// attributes are supported:
+///
+/// - `#[filter_args(rename = "name")]` changes the argument name.
+/// - `#[filter_args(default = expr)]` makes the argument optional, using `expr` if it's missing.
+/// - `#[filter_args(range = expr)]` checks that the value, or every element of an array, is in
+///   the given range, like `0..=255`.
+///
+/// # Example
+/// ```no_run
+/// # #[cfg(feature = "macros")]
+/// # {
+/// use vapoursynth::prelude::*;
+/// use vapoursynth::plugins::FilterArgs;
+///
+/// #[derive(FilterArgs)]
+/// struct BlurArgs<'core> {
+///     clip: Node<'core>,
+///     #[filter_args(default = 1, range = 1..=100)]
+///     radius: i64,
+///     #[filter_args(rename = "planes", range = 0..3)]
+///     plane_indices: Vec<i64>,
+///     strength: Option<f64>,
+/// }
+///
+/// assert_eq!(
+///     BlurArgs::args(),
+///     "clip:vnode;radius:int:opt;planes:int[]:empty;strength:float:opt;"
+/// );
+/// # }
+/// ```
+pub trait FilterArgs<'core>: Sized {
+    /// Returns the argument string for `FilterFunction::args()`.
+    fn args() -> &'static str;
+
+    /// Parses the arguments from the map passed to `FilterFunction::create()`.
+    fn from_map(map: &Map<'core>) -> Result<Self, ArgumentError>;
+}
+
+/// Retrieves a parameter, returning `Ok(None)` if it's missing.
+#[doc(hidden)]
+#[inline]
+pub fn get_parameter<'map, 'elem, T: FilterParameter<'map, 'elem>>(
+    map: &'map Map<'elem>,
+    key: &'static str,
+) -> Result<Option<T>, ArgumentError> {
+    if contains_key(map, key)? {
+        T::get_from_map(map, key).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Checks that all values of the parameter are in the range.
+#[doc(hidden)]
+pub fn check_range<'map, 'elem, T, R>(
+    value: &T,
+    range: R,
+    key: &'static str,
+    range_str: &'static str,
+) -> Result<(), ArgumentError>
+where
+    T: FilterParameter<'map, 'elem>,
+    T::Argument: PartialOrd + Debug,
+    R: std::ops::RangeBounds<T::Argument>,
+{
+    value.try_for_each_value(|x| {
+        if range.contains(x) {
+            Ok(())
+        } else {
+            Err(ArgumentError::OutOfRange {
+                key,
+                value: format!("{:?}", x),
+                range: range_str,
+            })
+        }
+    })
+}
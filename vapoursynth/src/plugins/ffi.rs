@@ -1,17 +1,20 @@
 //! Internal stuff for plugin FFI handling.
-use std::ffi::CString;
+use std::any::Any;
+use std::ffi::{CStr, CString};
 use std::fmt::Write;
 use std::ops::{Deref, DerefMut};
 use std::os::raw::c_void;
+use std::panic::AssertUnwindSafe;
 use std::ptr::{self};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{mem, panic, process};
 
 use vapoursynth_sys as ffi;
 
 use crate::api::API;
 use crate::core::CoreRef;
-use crate::map::{MapRef, MapRefMut};
-use crate::plugins::{Filter, FilterFunction, FrameContext, Metadata};
+use crate::map::{Map, MapRef, MapRefMut};
+use crate::plugins::{Filter, FilterFunction, FrameContext, Metadata, Version};
 use crate::video_info::VideoInfo;
 
 /// Container for the internal filter function data.
@@ -22,6 +25,24 @@ pub(crate) struct FilterFunctionData<F: FilterFunction> {
     pub name: CString,
 }
 
+/// Container for the internal filter instance data.
+struct FilterData<'core> {
+    filter: Box<dyn Filter<'core> + 'core>,
+    // Set when the filter panics, after which it isn't called anymore.
+    poisoned: AtomicBool,
+}
+
+/// Returns the message of a panic payload.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&'static str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
 /// Drops the filter.
 unsafe extern "C" fn free(
     instance_data: *mut c_void,
@@ -31,8 +52,8 @@ unsafe extern "C" fn free(
     let closure = move || {
         // The actual lifetime isn't 'static, it's 'core, but we don't really have a way of
         // retrieving it.
-        let filter = Box::from_raw(instance_data as *mut Box<dyn Filter<'static> + 'static>);
-        drop(filter);
+        let data = Box::from_raw(instance_data as *mut FilterData<'static>);
+        drop(data);
     };
 
     if panic::catch_unwind(closure).is_err() {
@@ -40,7 +61,16 @@ unsafe extern "C" fn free(
     }
 }
 
+/// Sets an error on the frame context.
+unsafe fn set_filter_error(api: API, frame_ctx: *mut ffi::VSFrameContext, message: &str) {
+    let message = CString::new(message.replace('\0', "\\0")).unwrap();
+    api.set_filter_error(message.as_ptr(), frame_ctx);
+}
+
 /// Calls `Filter::get_frame_initial()` and `Filter::get_frame()`.
+///
+/// If the filter panics, the panic is reported as a filter error and the filter is poisoned, so
+/// that all subsequent frame requests fail.
 unsafe extern "C" fn get_frame(
     n: i32,
     activation_reason: i32,
@@ -50,19 +80,30 @@ unsafe extern "C" fn get_frame(
     core: *mut ffi::VSCore,
     _vsapi: *const ffi::VSAPI,
 ) -> *const ffi::VSFrame {
+    let api = API::get_cached();
+
+    // The actual lifetime isn't 'static, it's 'core, but we don't really have a way of
+    // retrieving it.
+    let data = &*(instance_data as *const FilterData<'static>);
+
+    if data.poisoned.load(Ordering::Acquire) {
+        set_filter_error(
+            api,
+            frame_ctx,
+            &format!("Can't get frame {n}: the filter has panicked earlier"),
+        );
+        return ptr::null();
+    }
+
     let closure = move || {
-        let api = API::get_cached();
         let core = CoreRef::from_ptr(core);
         let context = FrameContext::from_ptr(frame_ctx);
-
-        // The actual lifetime isn't 'static, it's 'core, but we don't really have a way of
-        // retrieving it.
-        let filter = Box::from_raw(instance_data as *mut Box<dyn Filter<'static> + 'static>);
+        let filter = &data.filter;
 
         debug_assert!(n >= 0);
         let n = n as usize;
 
-        let rv = match activation_reason {
+        match activation_reason {
             x if x == ffi::VSActivationReason_arInitial as _ => {
                 match filter.get_frame_initial(api, core, context, n) {
                     Ok(Some(frame)) => {
@@ -77,9 +118,7 @@ unsafe extern "C" fn get_frame(
 
                         write!(buf, "Error in Filter::get_frame_initial(): {}", err).unwrap();
 
-                        let buf = CString::new(buf.replace('\0', "\\0")).unwrap();
-                        api.set_filter_error(buf.as_ptr(), frame_ctx);
-
+                        set_filter_error(api, frame_ctx, &buf);
                         ptr::null()
                     }
                 }
@@ -93,29 +132,78 @@ unsafe extern "C" fn get_frame(
                         ptr
                     }
                     Err(err) => {
-                        let buf = format!("{}", err);
-                        let buf = CString::new(buf.replace('\0', "\\0")).unwrap();
-                        api.set_filter_error(buf.as_ptr(), frame_ctx);
-
+                        set_filter_error(api, frame_ctx, &format!("{}", err));
                         ptr::null()
                     }
                 }
             }
             _ => ptr::null(),
-        };
-
-        mem::forget(filter);
-
-        rv
+        }
     };
 
-    match panic::catch_unwind(closure) {
+    // The filter is only accessed through a shared reference, and it's poisoned on panic, so it's
+    // never observed in a broken state.
+    match panic::catch_unwind(AssertUnwindSafe(closure)) {
         Ok(frame) => frame,
-        Err(_) => process::abort(),
+        Err(payload) => {
+            data.poisoned.store(true, Ordering::Release);
+            set_filter_error(
+                api,
+                frame_ctx,
+                &format!(
+                    "The filter panicked while getting frame {n}: {}",
+                    panic_message(&*payload)
+                ),
+            );
+            ptr::null()
+        }
     }
 }
 
-/// Creates a new instance of the filter.
+/// Creates a video filter node from `filter` and stores it in `out`.
+///
+/// # Safety
+/// The caller must ensure `out` is the output map of a function called by VapourSynth.
+pub(crate) unsafe fn create_video_filter<'core>(
+    api: API,
+    core: CoreRef<'core>,
+    out: &mut Map<'core>,
+    name: &CStr,
+    filter: Box<dyn Filter<'core> + 'core>,
+) {
+    // In v4, we need to get the video info before creating the filter
+    let vi = filter
+        .video_info(api, core)
+        .into_iter()
+        .map(VideoInfo::ffi_type)
+        .collect::<Vec<_>>();
+
+    // For now, assume single output (most common case)
+    // TODO: Handle multiple outputs if needed
+    let vi_ptr = if !vi.is_empty() {
+        vi.as_ptr()
+    } else {
+        ptr::null()
+    };
+
+    api.create_video_filter(
+        out.deref_mut(),
+        name.as_ptr(),
+        vi_ptr,
+        Some(get_frame),
+        Some(free),
+        ffi::VSFilterMode_fmParallel as i32,
+        ptr::null(), // No dependencies for now
+        0,           // numDeps
+        Box::into_raw(Box::new(FilterData {
+            filter,
+            poisoned: AtomicBool::new(false),
+        })) as *mut _,
+        core.ptr(),
+    );
+}
+
+/// Calls the filter function.
 pub(crate) unsafe extern "C" fn create<F: FilterFunction>(
     in_: *const ffi::VSMap,
     out: *mut ffi::VSMap,
@@ -123,77 +211,52 @@ pub(crate) unsafe extern "C" fn create<F: FilterFunction>(
     core: *mut ffi::VSCore,
     api: *const ffi::VSAPI,
 ) {
-    let closure = move || {
-        API::set(api);
-        let api = API::get_cached();
+    API::set(api);
+    let api = API::get_cached();
+    let data = &*(user_data as *const FilterFunctionData<F>);
 
+    let closure = move || {
         let args = MapRef::from_ptr(in_);
         let mut out = MapRefMut::from_ptr(out);
         let core = CoreRef::from_ptr(core);
-        let data = Box::from_raw(user_data as *mut FilterFunctionData<F>);
-
-        let filter = match data.filter_function.create(api, core, &args) {
-            Ok(Some(filter)) => Some(Box::new(filter)),
-            Ok(None) => None,
-            Err(err) => {
-                let mut buf = String::with_capacity(64);
-
-                write!(
-                    buf,
-                    "Error in Filter::create() of {}: {}",
-                    data.name.to_str().unwrap(),
-                    err
-                )
-                .unwrap();
-
-                write!(buf, "{}", err).unwrap();
-
-                out.set_error(&buf.replace('\0', "\\0")).unwrap();
-                None
-            }
-        };
-
-        if let Some(filter) = filter {
-            // In v4, we need to get the video info before creating the filter
-            let vi = filter
-                .video_info(api, core)
-                .into_iter()
-                .map(VideoInfo::ffi_type)
-                .collect::<Vec<_>>();
-
-            // For now, assume single output (most common case)
-            // TODO: Handle multiple outputs if needed
-            let vi_ptr = if !vi.is_empty() {
-                vi.as_ptr()
-            } else {
-                ptr::null()
-            };
 
-            api.create_video_filter(
-                out.deref_mut().deref_mut(),
-                data.name.as_ptr(),
-                vi_ptr,
-                Some(get_frame),
-                Some(free),
-                ffi::VSFilterMode_fmParallel as i32,
-                ptr::null(), // No dependencies for now
-                0,           // numDeps
-                Box::into_raw(filter) as *mut _,
-                core.ptr(),
-            );
-        }
+        if let Err(err) = data.filter_function.call(api, core, &args, &mut out) {
+            let mut buf = String::with_capacity(64);
+
+            write!(
+                buf,
+                "Error in FilterFunction::call() of {}: {:#}",
+                data.name.to_str().unwrap(),
+                err
+            )
+            .unwrap();
 
-        mem::forget(data);
+            out.set_error(&buf.replace('\0', "\\0")).unwrap();
+        }
     };
 
-    if panic::catch_unwind(closure).is_err() {
-        // The `FilterFunction` might have been left in an inconsistent state, so we have to abort.
-        process::abort();
+    // The filter function is only accessed through a shared reference, so it's up to its
+    // implementation to stay consistent if it panics, like with any `Sync` type.
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(closure)) {
+        let message = format!(
+            "FilterFunction::call() of {} panicked: {}",
+            data.name.to_string_lossy(),
+            panic_message(&*payload)
+        );
+
+        let mut out = MapRefMut::from_ptr(out);
+        out.set_error(&message.replace('\0', "\\0")).unwrap();
     }
 }
 
 /// Registers the plugin.
 ///
+/// The plugin is configured with `required_api_version` even if the host supports an older API
+/// version: VapourSynth then refuses to load the plugin and reports why. Returns `false` if the
+/// plugin won't be loaded, in which case the filter functions shouldn't be registered.
+///
+/// The metadata must be valid, which `export_vapoursynth_plugin!` checks at compile time.
+///
 /// This function is for internal use only.
 ///
 /// # Safety
@@ -203,7 +266,11 @@ pub unsafe fn call_config_func(
     vspapi: *const ffi::VSPLUGINAPI,
     plugin: *mut ffi::VSPlugin,
     metadata: Metadata,
-) {
+    version: Version,
+    required_api_version: Version,
+) -> bool {
+    debug_assert!(metadata.validate().is_ok());
+
     let identifier_cstring = CString::new(metadata.identifier)
         .expect("Couldn't convert the plugin identifier to a CString");
     let namespace_cstring = CString::new(metadata.namespace)
@@ -211,26 +278,31 @@ pub unsafe fn call_config_func(
     let name_cstring =
         CString::new(metadata.name).expect("Couldn't convert the plugin name to a CString");
 
-    let api_version = (ffi::VAPOURSYNTH_API_MAJOR << 16 | ffi::VAPOURSYNTH_API_MINOR) as i32;
     let flags = if metadata.read_only {
         0 // Read-only means NOT modifiable
     } else {
         ffi::VSPluginConfigFlags_pcModifiable as i32
     };
 
-    ((*vspapi).configPlugin.unwrap())(
+    let configured = ((*vspapi).configPlugin.unwrap())(
         identifier_cstring.as_ptr(),
         namespace_cstring.as_ptr(),
         name_cstring.as_ptr(),
-        1, // Plugin version
-        api_version,
+        version.ffi_type(),
+        required_api_version.ffi_type(),
         flags,
         plugin,
-    );
+    ) != 0;
+
+    let host_api_version = Version::from_ffi_type(((*vspapi).getAPIVersion.unwrap())());
+    configured && host_api_version >= required_api_version
 }
 
 /// Registers the filter `F`.
 ///
+/// Returns `false` if VapourSynth rejected the function, for example because of an invalid name
+/// or argument string. The reason is logged by the core.
+///
 /// This function is for internal use only.
 ///
 /// # Safety
@@ -240,27 +312,33 @@ pub unsafe fn call_register_func<F: FilterFunction>(
     vspapi: *const ffi::VSPLUGINAPI,
     plugin: *mut ffi::VSPlugin,
     filter_function: F,
-) {
+) -> bool {
     let name_cstring = CString::new(filter_function.name())
         .expect("Couldn't convert the filter name to a CString");
     let args_cstring = CString::new(filter_function.args())
         .expect("Couldn't convert the filter args to a CString");
-    let return_type_cstring =
-        CString::new("clip:vnode;").expect("Couldn't convert return type to a CString");
+    let return_type_cstring = CString::new(filter_function.return_type())
+        .expect("Couldn't convert the filter return type to a CString");
 
-    let data = Box::new(FilterFunctionData {
+    let data = Box::into_raw(Box::new(FilterFunctionData {
         filter_function,
         name: name_cstring,
-    });
+    }));
 
-    ((*vspapi).registerFunction.unwrap())(
-        data.name.as_ptr(),
+    let registered = ((*vspapi).registerFunction.unwrap())(
+        (*data).name.as_ptr(),
         args_cstring.as_ptr(),
         return_type_cstring.as_ptr(),
         Some(create::<F>),
-        Box::into_raw(data) as _,
+        data as _,
         plugin,
-    );
+    ) != 0;
+
+    if !registered {
+        drop(Box::from_raw(data));
+    }
+
+    registered
 }
 
 /// Exports a VapourSynth plugin from this library.
@@ -268,11 +346,21 @@ pub unsafe fn call_register_func<F: FilterFunction>(
 /// This macro should be used only once at the top level of the library. The library should have a
 /// `cdylib` crate type.
 ///
-/// The first parameter is a `Metadata` expression containing your plugin's metadata.
+/// The first parameter is a `Metadata` expression containing your plugin's metadata. It must be
+/// a constant expression, like a `Metadata` literal made of string literals or a `const` item,
+/// since the metadata is validated at compile time: an invalid identifier, namespace or name fails
+/// the build. Metadata computed at runtime doesn't compile.
 ///
 /// Following it is a list of values implementing `FilterFunction`, those are the filter functions
 /// the plugin will export.
 ///
+/// Optionally, the plugin version and the minimum VapourSynth API version required by the plugin
+/// can be given as `version = ...` and `required_api_version = ...`, in this order. They are
+/// `Version` expressions and default to the major and minor version of the crate exporting the
+/// plugin, taken from `CARGO_PKG_VERSION`, and `Version::API`, the API version this crate was built
+/// against. VapourSynth refuses to load the plugin if it supports an older API version than the
+/// required one.
+///
 /// # Example
 /// ```ignore
 /// export_vapoursynth_plugin! {
@@ -282,12 +370,20 @@ pub unsafe fn call_register_func<F: FilterFunction>(
 ///         name: "Invert Example Plugin",
 ///         read_only: true,
 ///     },
-///     [SampleFilterFunction::new(), OtherFunction::new()]
+///     [SampleFilterFunction::new(), OtherFunction::new()],
+///     version = Version::new(1, 2),
 /// }
 /// ```
 #[macro_export]
 macro_rules! export_vapoursynth_plugin {
-    ($metadata:expr, [$($filter:expr),*$(,)*]) => (
+    (
+        $metadata:expr, [$($filter:expr),*$(,)*]
+        $(, version = $version:expr)?
+        $(, required_api_version = $required_api_version:expr)?
+        $(,)?
+    ) => (
+        const _: () = $crate::plugins::assert_valid($crate::plugins::Metadata::validate(&$metadata));
+
         #[allow(non_snake_case)]
         #[unsafe(no_mangle)]
         pub unsafe extern "C" fn VapourSynthPluginInit2(
@@ -298,11 +394,16 @@ macro_rules! export_vapoursynth_plugin {
             use $crate::plugins::ffi::{call_config_func, call_register_func};
 
             let closure = move || {
-                call_config_func(vspapi, plugin, $metadata);
-
-                $(
-                    call_register_func(vspapi, plugin, $filter);
-                )*
+                let version = None$(.or(Some($version)))?
+                    .unwrap_or_else(|| $crate::plugins::Version::parse(env!("CARGO_PKG_VERSION")));
+                let required_api_version = None$(.or(Some($required_api_version)))?
+                    .unwrap_or($crate::plugins::Version::API);
+
+                if call_config_func(vspapi, plugin, $metadata, version, required_api_version) {
+                    $(
+                        call_register_func(vspapi, plugin, $filter);
+                    )*
+                }
             };
 
             if panic::catch_unwind(closure).is_err() {
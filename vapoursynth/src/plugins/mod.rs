@@ -1,5 +1,8 @@
 //! Things related to making VapourSynth plugins.
 
+use std::ffi::CString;
+use std::fmt::{self, Debug};
+
 use anyhow::Result;
 
 use crate::api::API;
@@ -10,9 +13,24 @@ use crate::map::{self, Map, Value, ValueIter};
 use crate::node::Node;
 use crate::video_info::VideoInfo;
 
+mod args;
+pub use self::args::{ArgumentError, FilterArgs};
+#[doc(hidden)]
+pub use self::args::{check_range, get_parameter};
+#[cfg(feature = "macros")]
+pub use vapoursynth_macros::FilterArgs;
+
 mod frame_context;
 pub use self::frame_context::FrameContext;
 
+mod validation;
+#[doc(hidden)]
+pub use self::validation::assert_valid;
+pub use self::validation::{
+    ValidationError, validate_function_name, validate_identifier, validate_namespace,
+    validate_signature,
+};
+
 pub mod ffi;
 
 /// Plugin metadata.
@@ -42,9 +60,94 @@ pub struct Metadata {
     pub read_only: bool,
 }
 
+/// A version made of a major and a minor number, used by VapourSynth for plugin and API versions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Version {
+    /// The major version number.
+    pub major: u16,
+
+    /// The minor version number.
+    pub minor: u16,
+}
+
+impl Version {
+    /// The VapourSynth API version this crate was built against.
+    pub const API: Self = Self::new(
+        vapoursynth_sys::VAPOURSYNTH_API_MAJOR as u16,
+        vapoursynth_sys::VAPOURSYNTH_API_MINOR as u16,
+    );
+
+    /// Creates a new `Version`.
+    #[inline]
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
+    }
+
+    /// Parses the major and minor numbers of a version string like `CARGO_PKG_VERSION`.
+    ///
+    /// Everything after the minor number, like the patch number or a pre-release suffix, is
+    /// ignored. Missing numbers are parsed as zero and numbers which are too large saturate.
+    ///
+    /// # Example
+    /// ```
+    /// use vapoursynth::plugins::Version;
+    ///
+    /// assert_eq!(Version::parse("1.2.3-beta.1"), Version::new(1, 2));
+    /// assert_eq!(Version::parse("3"), Version::new(3, 0));
+    /// ```
+    pub const fn parse(version: &str) -> Self {
+        let bytes = version.as_bytes();
+        let (major, i) = parse_number(bytes, 0);
+
+        let minor = if i < bytes.len() && bytes[i] == b'.' {
+            parse_number(bytes, i + 1).0
+        } else {
+            0
+        };
+
+        Self::new(major, minor)
+    }
+
+    /// Converts the version into the VapourSynth representation.
+    #[inline]
+    pub(crate) const fn ffi_type(self) -> i32 {
+        ((self.major as i32) << 16) | self.minor as i32
+    }
+
+    /// Converts the VapourSynth representation into a `Version`.
+    #[inline]
+    pub(crate) const fn from_ffi_type(x: i32) -> Self {
+        Self::new((x >> 16) as u16, x as u16)
+    }
+}
+
+impl fmt::Display for Version {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Parses a decimal number starting at `i`, returning it and the index after it.
+const fn parse_number(bytes: &[u8], mut i: usize) -> (u16, usize) {
+    let mut number = 0u16;
+
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        number = number
+            .saturating_mul(10)
+            .saturating_add((bytes[i] - b'0') as u16);
+        i += 1;
+    }
+
+    (number, i)
+}
+
 /// A filter function interface.
 ///
 /// See the `make_filter_function!` macro that generates types implementing this automatically.
+/// For filters with many arguments, consider implementing it by hand with the help of a struct
+/// implementing `FilterArgs`. Functions which aren't filters can override `return_type()` and
+/// `call()` to return arbitrary values.
 pub trait FilterFunction: Send + Sync {
     /// Returns the name of the function.
     ///
@@ -63,24 +166,43 @@ pub trait FilterFunction: Send + Sync {
     /// - The argument name. The same characters are allowed as for the filter's name. Argument
     ///   names should be all lowercase and use only letters and the underscore.
     ///
-    /// - The type. One of `int`, `float`, `data`, `clip`, `frame`, `func`. They correspond to the
-    ///   `Map::get_*()` functions (`clip` is `get_node()`). It's possible to declare an array by
-    ///   appending `[]` to the type.
+    /// - The type. One of `int`, `float`, `data`, `anode`, `vnode`, `aframe`, `vframe`, `func`.
+    ///   They correspond to the `Map::get_*()` functions (`vnode` is `get_video_node()`). It's
+    ///   possible to declare an array by appending `[]` to the type.
     ///
     /// - `opt` if the parameter is optional.
     ///
     /// - `empty` if the array is allowed to be empty.
     ///
     /// The following example declares the arguments "blah", "moo", and "asdf":
-    /// `blah:clip;moo:int[]:opt;asdf:float:opt;`
+    /// `blah:vnode;moo:int[]:opt;asdf:float:opt;`
+    ///
+    /// The string can be checked with `validate_signature()`, which is a `const fn`. Functions
+    /// with invalid names or argument strings aren't registered.
     fn args(&self) -> &str;
 
+    /// Returns the return type string.
+    ///
+    /// It has the same format as the argument string. The default is `clip:vnode;`, which is what
+    /// filters return. Functions which aren't filters should override this together with
+    /// `call()`.
+    ///
+    /// For example, a function returning an integer and several clips can use
+    /// `count:int;clips:vnode[];`.
+    #[inline]
+    fn return_type(&self) -> &str {
+        "clip:vnode;"
+    }
+
     /// The callback for this filter function.
     ///
     /// In most cases this is where you should create a new instance of the filter and return it.
     /// However, a filter function like AviSynth compat's `LoadPlugin()` which isn't actually a
     /// filter, can return `None`.
     ///
+    /// The default implementation returns `None`, which is useful for functions overriding
+    /// `call()`.
+    ///
     /// `args` contains the filter arguments, as specified by the argument string from
     /// `FilterFunction::args()`. Their presence and types are validated by VapourSynth so it's
     /// safe to `unwrap()`.
@@ -89,15 +211,82 @@ pub trait FilterFunction: Send + Sync {
     /// so that you can request their frames in `get_frame_initial()`.
     // TODO: with generic associated types it'll be possible to make Filter<'core> an associated
     // type of this trait and get rid of this Box.
+    #[inline]
     fn create<'core>(
         &self,
         api: API,
         core: CoreRef<'core>,
         args: &Map<'core>,
-    ) -> Result<Option<Box<dyn Filter<'core> + 'core>>>;
+    ) -> Result<Option<Box<dyn Filter<'core> + 'core>>> {
+        let _ = (api, core, args);
+        Ok(None)
+    }
+
+    /// The function called by VapourSynth.
+    ///
+    /// The default implementation calls `create()` and stores the resulting filter's clip in
+    /// `out`. Functions which aren't filters, like utility functions returning integers, strings
+    /// or several clips, can override it to store the values declared by `return_type()` in `out`
+    /// directly.
+    ///
+    /// Errors and panics are reported to VapourSynth as errors of the function call.
+    ///
+    /// # Example
+    /// ```
+    /// use anyhow::Result;
+    /// use vapoursynth::core::CoreRef;
+    /// use vapoursynth::plugins::FilterFunction;
+    /// use vapoursynth::prelude::*;
+    ///
+    /// struct SumFunction;
+    ///
+    /// impl FilterFunction for SumFunction {
+    ///     fn name(&self) -> &str {
+    ///         "Sum"
+    ///     }
+    ///
+    ///     fn args(&self) -> &str {
+    ///         "values:int[];"
+    ///     }
+    ///
+    ///     fn return_type(&self) -> &str {
+    ///         "sum:int;"
+    ///     }
+    ///
+    ///     fn call<'core>(
+    ///         &self,
+    ///         _api: API,
+    ///         _core: CoreRef<'core>,
+    ///         args: &Map<'core>,
+    ///         out: &mut Map<'core>,
+    ///     ) -> Result<()> {
+    ///         let sum = args.get_int_array("values")?.iter().sum();
+    ///         out.set_int("sum", sum)?;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    fn call<'core>(
+        &self,
+        api: API,
+        core: CoreRef<'core>,
+        args: &Map<'core>,
+        out: &mut Map<'core>,
+    ) -> Result<()> {
+        if let Some(filter) = self.create(api, core, args)? {
+            let name = CString::new(self.name())?;
+            unsafe { ffi::create_video_filter(api, core, out, &name, filter) };
+        }
+
+        Ok(())
+    }
 }
 
 /// A filter interface.
+///
+/// If `get_frame_initial()` or `get_frame()` panics, the panic is reported as an error for the
+/// requested frame and the filter is poisoned: all subsequent frame requests fail without calling
+/// the filter.
 // TODO: perhaps it's possible to figure something out about Send + Sync with specialization? Since
 // there are Node flags which say that the filter will be called strictly by one thread, in which
 // case Sync shouldn't be required.
@@ -143,14 +332,30 @@ pub trait Filter<'core>: Send + Sync {
 }
 
 /// An internal trait representing a filter argument type.
-pub trait FilterArgument<'map, 'elem: 'map>: Value<'map, 'elem> + private::Sealed {
+pub trait FilterArgument<'map, 'elem>: Sized + private::Sealed {
     /// Returns the VapourSynth type name for this argument type.
     fn type_name() -> &'static str;
+
+    /// Retrieves the first value of the argument from the given map.
+    fn get_argument(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError>;
+
+    /// Retrieves all values of the argument from the given map.
+    fn get_arguments(map: &'map Map<'elem>, key: &'static str) -> Result<Vec<Self>, ArgumentError>;
 }
 
 /// An internal trait representing a filter parameter type (argument type + whether it's an array
 /// or optional).
-pub trait FilterParameter<'map, 'elem: 'map>: private::Sealed {
+///
+/// This is the single set of argument conversions shared by `make_filter_function!`, the
+/// `filter_function` attribute, `FilterArgs` fields and `Function::typed()` closures.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be used as a filter parameter",
+    label = "unsupported filter parameter type",
+    note = "supported types are integers, floats, `bool`, `&str`, `String`, `&[u8]`, `Node`, \
+            `FrameRef`, `Function` and types implementing `EnumArgument`, optionally wrapped in \
+            `Vec<T>` or `ValueIter<T>` for arrays and `Option<T>` for optional parameters"
+)]
+pub trait FilterParameter<'map, 'elem>: Sized + private::Sealed {
     /// The underlying argument type for this parameter type.
     type Argument: FilterArgument<'map, 'elem>;
 
@@ -161,52 +366,268 @@ pub trait FilterParameter<'map, 'elem: 'map>: private::Sealed {
     fn is_optional() -> bool;
 
     /// Retrieves this parameter from the given map.
-    fn get_from_map(map: &'map Map<'elem>, key: &str) -> Self;
+    fn get_from_map(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError>;
+
+    /// Calls `f` with every value of this parameter, stopping at the first error.
+    #[doc(hidden)]
+    fn try_for_each_value<E, F>(&self, f: F) -> Result<(), E>
+    where
+        F: FnMut(&Self::Argument) -> Result<(), E>;
+}
+
+/// A user enum which can be used as a filter argument.
+///
+/// The enum is passed to the filter as a value of its `Repr` type, usually `i64` or `&str`, and
+/// converted with `from_repr()`. Values which don't correspond to any variant are reported as
+/// filter creation errors.
+///
+/// # Example
+/// ```
+/// use vapoursynth::plugins::EnumArgument;
+///
+/// enum Kernel {
+///     Bilinear,
+///     Bicubic,
+/// }
+///
+/// impl EnumArgument for Kernel {
+///     type Repr<'map> = &'map str;
+///
+///     fn from_repr(x: &str) -> Option<Self> {
+///         match x {
+///             "bilinear" => Some(Kernel::Bilinear),
+///             "bicubic" => Some(Kernel::Bicubic),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub trait EnumArgument: Sized {
+    /// The argument type the enum is passed as.
+    type Repr<'map>;
+
+    /// Converts the argument value into the enum, returning `None` for invalid values.
+    fn from_repr(x: Self::Repr<'_>) -> Option<Self>;
+}
+
+/// Wraps a map error for the argument with the given key.
+///
+/// A missing key is reported as `ArgumentError::Missing`.
+#[inline]
+fn map_error(key: &'static str) -> impl FnOnce(map::Error) -> ArgumentError {
+    move |source| match source {
+        map::Error::KeyNotFound => ArgumentError::Missing { key },
+        source => ArgumentError::Map { key, source },
+    }
+}
+
+macro_rules! impl_filter_argument {
+    (
+        $type:ty,
+        $type_name:expr,
+        |$map:ident, $key:ident| $get:expr,
+        |$all_map:ident, $all_key:ident| $get_all:expr
+    ) => {
+        impl<'map, 'elem> FilterArgument<'map, 'elem> for $type {
+            #[inline]
+            fn type_name() -> &'static str {
+                $type_name
+            }
+
+            #[inline]
+            fn get_argument(
+                $map: &'map Map<'elem>,
+                $key: &'static str,
+            ) -> Result<Self, ArgumentError> {
+                $get.map_err(map_error($key))
+            }
+
+            #[inline]
+            fn get_arguments(
+                $all_map: &'map Map<'elem>,
+                $all_key: &'static str,
+            ) -> Result<Vec<Self>, ArgumentError> {
+                $get_all.map_err(map_error($all_key))
+            }
+        }
+    };
+}
+
+impl_filter_argument!(i64, "int", |map, key| map.get_int(key), |map, key| map
+    .get_int_array(key)
+    .map(<[_]>::to_vec));
+impl_filter_argument!(f64, "float", |map, key| map.get_float(key), |map, key| map
+    .get_float_array(key)
+    .map(<[_]>::to_vec));
+impl_filter_argument!(f32, "float", |map, key| map.get_f32(key), |map, key| map
+    .get_float_iter(key)
+    .map(|iter| iter.map(|x| x as f32).collect()));
+impl_filter_argument!(
+    &'map [u8],
+    "data",
+    |map, key| map.get_data(key),
+    |map, key| map.get_data_iter(key).map(Iterator::collect)
+);
+impl_filter_argument!(
+    &'map str,
+    "data",
+    |map, key| map.get_str(key),
+    |map, key| map.get_str_iter(key).map(Iterator::collect)
+);
+impl_filter_argument!(
+    String,
+    "data",
+    |map, key| map.get_str(key).map(str::to_owned),
+    |map, key| map
+        .get_str_iter(key)
+        .map(|iter| iter.map(str::to_owned).collect())
+);
+impl_filter_argument!(
+    Node<'elem>,
+    "vnode",
+    |map, key| map.get_video_node(key),
+    |map, key| map.get_video_node_iter(key).map(Iterator::collect)
+);
+impl_filter_argument!(
+    FrameRef<'elem>,
+    "vframe",
+    |map, key| map.get_video_frame(key),
+    |map, key| map.get_video_frame_iter(key).map(Iterator::collect)
+);
+impl_filter_argument!(
+    Function<'elem>,
+    "func",
+    |map, key| map.get_function(key),
+    |map, key| map.get_function_iter(key).map(Iterator::collect)
+);
+
+/// Converts an integer argument into a narrower integer type.
+#[inline]
+fn narrow_int<T: TryFrom<i64>>(key: &'static str, value: i64) -> Result<T, ArgumentError> {
+    T::try_from(value).map_err(|_| ArgumentError::Overflow {
+        key,
+        value,
+        target: std::any::type_name::<T>(),
+    })
+}
+
+macro_rules! impl_filter_argument_narrow_int {
+    ($($type:ty),*) => {
+        $(
+            impl<'map, 'elem> FilterArgument<'map, 'elem> for $type {
+                #[inline]
+                fn type_name() -> &'static str {
+                    "int"
+                }
+
+                #[inline]
+                fn get_argument(
+                    map: &'map Map<'elem>,
+                    key: &'static str,
+                ) -> Result<Self, ArgumentError> {
+                    narrow_int(key, i64::get_argument(map, key)?)
+                }
+
+                #[inline]
+                fn get_arguments(
+                    map: &'map Map<'elem>,
+                    key: &'static str,
+                ) -> Result<Vec<Self>, ArgumentError> {
+                    i64::get_arguments(map, key)?
+                        .into_iter()
+                        .map(|x| narrow_int(key, x))
+                        .collect()
+                }
+            }
+        )*
+    };
 }
 
-impl<'map, 'elem: 'map> FilterArgument<'map, 'elem> for i64 {
+impl_filter_argument_narrow_int!(i32, u32, usize);
+
+/// Converts an integer argument into a `bool`, accepting only `0` and `1` like VapourSynth does.
+#[inline]
+fn int_to_bool(key: &'static str, value: i64) -> Result<bool, ArgumentError> {
+    match value {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(ArgumentError::InvalidValue {
+            key,
+            value: value.to_string(),
+        }),
+    }
+}
+
+impl<'map, 'elem> FilterArgument<'map, 'elem> for bool {
     #[inline]
     fn type_name() -> &'static str {
         "int"
     }
-}
 
-impl<'map, 'elem: 'map> FilterArgument<'map, 'elem> for f64 {
     #[inline]
-    fn type_name() -> &'static str {
-        "float"
+    fn get_argument(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError> {
+        int_to_bool(key, i64::get_argument(map, key)?)
     }
-}
 
-impl<'map, 'elem: 'map> FilterArgument<'map, 'elem> for &'map [u8] {
     #[inline]
-    fn type_name() -> &'static str {
-        "data"
+    fn get_arguments(map: &'map Map<'elem>, key: &'static str) -> Result<Vec<Self>, ArgumentError> {
+        i64::get_arguments(map, key)?
+            .into_iter()
+            .map(|x| int_to_bool(key, x))
+            .collect()
     }
 }
 
-impl<'map, 'elem: 'map> FilterArgument<'map, 'elem> for Node<'elem> {
+impl<'map, 'elem, T> FilterArgument<'map, 'elem> for T
+where
+    T: EnumArgument,
+    T::Repr<'map>: FilterArgument<'map, 'elem> + Debug,
+{
     #[inline]
     fn type_name() -> &'static str {
-        "vnode"
+        <T::Repr<'map>>::type_name()
     }
-}
 
-impl<'map, 'elem: 'map> FilterArgument<'map, 'elem> for FrameRef<'elem> {
     #[inline]
-    fn type_name() -> &'static str {
-        "vframe"
+    fn get_argument(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError> {
+        let value = <T::Repr<'map>>::get_argument(map, key)?;
+        from_repr(key, value)
     }
-}
 
-impl<'map, 'elem: 'map> FilterArgument<'map, 'elem> for Function<'elem> {
     #[inline]
-    fn type_name() -> &'static str {
-        "func"
+    fn get_arguments(map: &'map Map<'elem>, key: &'static str) -> Result<Vec<Self>, ArgumentError> {
+        <T::Repr<'map>>::get_arguments(map, key)?
+            .into_iter()
+            .map(|x| from_repr(key, x))
+            .collect()
+    }
+}
+
+/// Converts an argument value into a user enum.
+#[inline]
+fn from_repr<'map, T>(key: &'static str, value: T::Repr<'map>) -> Result<T, ArgumentError>
+where
+    T: EnumArgument,
+    T::Repr<'map>: Debug,
+{
+    let description = format!("{:?}", value);
+    T::from_repr(value).ok_or(ArgumentError::InvalidValue {
+        key,
+        value: description,
+    })
+}
+
+/// Returns whether the map contains the key.
+#[inline]
+pub(crate) fn contains_key(map: &Map, key: &'static str) -> Result<bool, ArgumentError> {
+    match map.value_count(key) {
+        Ok(_) => Ok(true),
+        Err(map::Error::KeyNotFound) => Ok(false),
+        Err(e) => Err(map_error(key)(e)),
     }
 }
 
-impl<'map, 'elem: 'map, T> FilterParameter<'map, 'elem> for T
+impl<'map, 'elem, T> FilterParameter<'map, 'elem> for T
 where
     T: FilterArgument<'map, 'elem>,
 {
@@ -223,12 +644,20 @@ where
     }
 
     #[inline]
-    fn get_from_map(map: &'map Map<'elem>, key: &str) -> Self {
-        Self::get_from_map(map, key).unwrap()
+    fn get_from_map(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError> {
+        T::get_argument(map, key)
+    }
+
+    #[inline]
+    fn try_for_each_value<E, F>(&self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&Self::Argument) -> Result<(), E>,
+    {
+        f(self)
     }
 }
 
-impl<'map, 'elem: 'map, T> FilterParameter<'map, 'elem> for Option<T>
+impl<'map, 'elem, T> FilterParameter<'map, 'elem> for Option<T>
 where
     T: FilterArgument<'map, 'elem>,
 {
@@ -245,16 +674,24 @@ where
     }
 
     #[inline]
-    fn get_from_map(map: &'map Map<'elem>, key: &str) -> Self {
-        match <Self::Argument as Value>::get_from_map(map, key) {
-            Ok(x) => Some(x),
-            Err(map::Error::KeyNotFound) => None,
-            _ => unreachable!(),
+    fn get_from_map(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError> {
+        if contains_key(map, key)? {
+            T::get_argument(map, key).map(Some)
+        } else {
+            Ok(None)
         }
     }
+
+    #[inline]
+    fn try_for_each_value<E, F>(&self, f: F) -> Result<(), E>
+    where
+        F: FnMut(&Self::Argument) -> Result<(), E>,
+    {
+        self.iter().try_for_each(f)
+    }
 }
 
-impl<'map, 'elem: 'map, T> FilterParameter<'map, 'elem> for ValueIter<'map, 'elem, T>
+impl<'map, 'elem, T> FilterParameter<'map, 'elem> for Vec<T>
 where
     T: FilterArgument<'map, 'elem>,
 {
@@ -271,12 +708,20 @@ where
     }
 
     #[inline]
-    fn get_from_map(map: &'map Map<'elem>, key: &str) -> Self {
-        <Self::Argument>::get_iter_from_map(map, key).unwrap()
+    fn get_from_map(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError> {
+        T::get_arguments(map, key)
+    }
+
+    #[inline]
+    fn try_for_each_value<E, F>(&self, f: F) -> Result<(), E>
+    where
+        F: FnMut(&Self::Argument) -> Result<(), E>,
+    {
+        self.iter().try_for_each(f)
     }
 }
 
-impl<'map, 'elem: 'map, T> FilterParameter<'map, 'elem> for Option<ValueIter<'map, 'elem, T>>
+impl<'map, 'elem, T> FilterParameter<'map, 'elem> for Option<Vec<T>>
 where
     T: FilterArgument<'map, 'elem>,
 {
@@ -293,32 +738,147 @@ where
     }
 
     #[inline]
-    fn get_from_map(map: &'map Map<'elem>, key: &str) -> Self {
-        match <Self::Argument as Value>::get_iter_from_map(map, key) {
-            Ok(x) => Some(x),
-            Err(map::Error::KeyNotFound) => None,
-            _ => unreachable!(),
+    fn get_from_map(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError> {
+        if contains_key(map, key)? {
+            T::get_arguments(map, key).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[inline]
+    fn try_for_each_value<E, F>(&self, f: F) -> Result<(), E>
+    where
+        F: FnMut(&Self::Argument) -> Result<(), E>,
+    {
+        self.iter().flatten().try_for_each(f)
+    }
+}
+
+impl<'map, 'elem, T> FilterParameter<'map, 'elem> for ValueIter<'map, 'elem, T>
+where
+    T: FilterArgument<'map, 'elem> + Value<'map, 'elem> + Clone,
+    ValueIter<'map, 'elem, T>: Iterator<Item = T>,
+{
+    type Argument = T;
+
+    #[inline]
+    fn is_array() -> bool {
+        true
+    }
+
+    #[inline]
+    fn is_optional() -> bool {
+        false
+    }
+
+    #[inline]
+    fn get_from_map(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError> {
+        <T as Value>::get_iter_from_map(map, key).map_err(map_error(key))
+    }
+
+    #[inline]
+    fn try_for_each_value<E, F>(&self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&Self::Argument) -> Result<(), E>,
+    {
+        self.clone().try_for_each(|x| f(&x))
+    }
+}
+
+impl<'map, 'elem, T> FilterParameter<'map, 'elem> for Option<ValueIter<'map, 'elem, T>>
+where
+    T: FilterArgument<'map, 'elem> + Value<'map, 'elem> + Clone,
+    ValueIter<'map, 'elem, T>: Iterator<Item = T>,
+{
+    type Argument = T;
+
+    #[inline]
+    fn is_array() -> bool {
+        true
+    }
+
+    #[inline]
+    fn is_optional() -> bool {
+        true
+    }
+
+    #[inline]
+    fn get_from_map(map: &'map Map<'elem>, key: &'static str) -> Result<Self, ArgumentError> {
+        match <T as Value>::get_iter_from_map(map, key) {
+            Ok(x) => Ok(Some(x)),
+            Err(map::Error::KeyNotFound) => Ok(None),
+            Err(e) => Err(map_error(key)(e)),
         }
     }
+
+    #[inline]
+    fn try_for_each_value<E, F>(&self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&Self::Argument) -> Result<(), E>,
+    {
+        self.iter().flat_map(Clone::clone).try_for_each(|x| f(&x))
+    }
+}
+
+/// Appends the parameter declaration to the argument string.
+///
+/// Parameters with a default value are declared optional. Array parameters can be empty.
+#[doc(hidden)]
+#[inline]
+pub fn push_parameter<'map, 'elem, T: FilterParameter<'map, 'elem>>(
+    args: &mut String,
+    name: &str,
+    has_default: bool,
+) {
+    *args += name;
+    *args += ":";
+    *args += <T::Argument as FilterArgument>::type_name();
+
+    if T::is_array() {
+        *args += "[]";
+    }
+
+    if T::is_optional() || has_default {
+        *args += ":opt";
+    }
+
+    if T::is_array() {
+        *args += ":empty";
+    }
+
+    *args += ";";
 }
 
 mod private {
-    use super::{FilterArgument, FrameRef, Function, Node, ValueIter};
+    use super::{EnumArgument, FilterArgument, FrameRef, Function, Node, ValueIter};
 
     pub trait Sealed {}
 
     impl Sealed for i64 {}
+    impl Sealed for i32 {}
+    impl Sealed for u32 {}
+    impl Sealed for usize {}
     impl Sealed for f64 {}
+    impl Sealed for f32 {}
+    impl Sealed for bool {}
     impl Sealed for &[u8] {}
+    impl Sealed for &str {}
+    impl Sealed for String {}
     impl<'elem> Sealed for Node<'elem> {}
     impl<'elem> Sealed for FrameRef<'elem> {}
     impl<'elem> Sealed for Function<'elem> {}
+    impl<T: EnumArgument> Sealed for T {}
+
+    impl<'map, 'elem, T> Sealed for Option<T> where T: FilterArgument<'map, 'elem> {}
+
+    impl<'map, 'elem, T> Sealed for Vec<T> where T: FilterArgument<'map, 'elem> {}
 
-    impl<'map, 'elem: 'map, T> Sealed for Option<T> where T: FilterArgument<'map, 'elem> {}
+    impl<'map, 'elem, T> Sealed for Option<Vec<T>> where T: FilterArgument<'map, 'elem> {}
 
-    impl<'map, 'elem: 'map, T> Sealed for ValueIter<'map, 'elem, T> where T: FilterArgument<'map, 'elem> {}
+    impl<'map, 'elem, T> Sealed for ValueIter<'map, 'elem, T> where T: FilterArgument<'map, 'elem> {}
 
-    impl<'map, 'elem: 'map, T> Sealed for Option<ValueIter<'map, 'elem, T>> where
+    impl<'map, 'elem, T> Sealed for Option<ValueIter<'map, 'elem, T>> where
         T: FilterArgument<'map, 'elem>
     {
     }
@@ -347,7 +907,15 @@ mod private {
 /// ```
 ///
 /// All VapourSynth-supported types can be used, as well as `Option<T>` for optional parameters and
-/// `ValueIter<T>` for array parameters. Array parameters can be empty.
+/// `ValueIter<T>` for array parameters. `bool` parameters are passed as integers and only accept
+/// `0` and `1`.
+///
+/// Array parameters are declared with `empty`, so they accept empty arrays. Previously they
+/// weren't, so VapourSynth rejected empty arrays before calling the create function: filters
+/// which relied on that have to check for empty arrays themselves.
+///
+/// The filter name and the parameter names are validated at compile time, so they must match
+/// `^[a-zA-Z][a-zA-Z0-9_]*$`.
 ///
 /// Caveat: the macro doesn't currently allow specifying mutable parameters, so to do that they
 /// have to be reassigned to a mutable variable in the function body. This is mainly a problem for
@@ -388,30 +956,31 @@ macro_rules! make_filter_function {
             $($body:tt)*
         }
     ) => (
+        const _: () = {
+            $crate::plugins::assert_valid($crate::plugins::validate_function_name($function_name));
+            $(
+                $crate::plugins::assert_valid(
+                    $crate::plugins::validate_function_name(stringify!($arg_name)),
+                );
+            )*
+        };
+
         struct $struct_name {
             args: String,
         }
 
         impl $struct_name {
+            #[allow(clippy::extra_unused_lifetimes)]
             fn new<'core>() -> Self {
                 let mut args = String::new();
 
                 $(
-                    // Don't use format!() for better constant propagation.
-                    args += stringify!($arg_name); // TODO: allow using a different name.
-                    args += ":";
-                    args
-                        += <<$arg_type as $crate::plugins::FilterParameter>::Argument>::type_name();
-
-                    if <$arg_type as $crate::plugins::FilterParameter>::is_array() {
-                        args += "[]";
-                    }
-
-                    if <$arg_type as $crate::plugins::FilterParameter>::is_optional() {
-                        args += ":opt";
-                    }
-
-                    args += ";";
+                    // TODO: allow using a different name.
+                    $crate::plugins::push_parameter::<$arg_type>(
+                        &mut args,
+                        stringify!($arg_name),
+                        false,
+                    );
                 )*
 
                 Self { args }
@@ -443,7 +1012,7 @@ macro_rules! make_filter_function {
                         <$arg_type as $crate::plugins::FilterParameter>::get_from_map(
                             args,
                             stringify!($arg_name),
-                        )
+                        )?
                     ),*
                 )
             }
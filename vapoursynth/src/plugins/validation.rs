@@ -0,0 +1,251 @@
+//! Validation of plugin metadata and function signatures.
+//!
+//! All checks are `const fn`s, so they can be evaluated at compile time. The
+//! `export_vapoursynth_plugin!` and `make_filter_function!` macros use them to make invalid
+//! plugin metadata and function names fail the build.
+
+use thiserror::Error;
+
+use super::Metadata;
+
+/// The error type for invalid plugin metadata and function signatures.
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[error("{}", self.message())]
+pub enum ValidationError {
+    InvalidIdentifier,
+    InvalidNamespace,
+    InvalidPluginName,
+    InvalidFunctionName,
+    InvalidArgumentName,
+    MissingArgumentType,
+    InvalidArgumentType,
+    InvalidArgumentModifier,
+}
+
+impl ValidationError {
+    /// Returns the error message.
+    ///
+    /// Unlike `to_string()`, this can be used in a constant context.
+    pub const fn message(self) -> &'static str {
+        match self {
+            ValidationError::InvalidIdentifier => {
+                "The plugin identifier must be non-empty and contain no whitespace or NUL \
+                 characters"
+            }
+            ValidationError::InvalidNamespace => {
+                "The plugin namespace must start with a lowercase letter and contain only \
+                 lowercase letters, digits and the underscore"
+            }
+            ValidationError::InvalidPluginName => {
+                "The plugin name must be non-empty and contain no NUL characters"
+            }
+            ValidationError::InvalidFunctionName => {
+                "The function name must start with a letter and contain only letters, digits \
+                 and the underscore"
+            }
+            ValidationError::InvalidArgumentName => {
+                "An argument name must start with a letter and contain only letters, digits and \
+                 the underscore"
+            }
+            ValidationError::MissingArgumentType => "An argument is missing its type",
+            ValidationError::InvalidArgumentType => {
+                "An argument type must be one of int, float, data, anode, vnode, aframe, vframe \
+                 and func, optionally followed by []"
+            }
+            ValidationError::InvalidArgumentModifier => {
+                "An argument modifier must be one of opt, empty and any"
+            }
+        }
+    }
+}
+
+/// The types allowed in argument and return type strings.
+const TYPES: &[&[u8]] = &[
+    b"int", b"float", b"data", b"anode", b"vnode", b"aframe", b"vframe", b"func",
+];
+
+/// The modifiers allowed in argument and return type strings.
+const MODIFIERS: &[&[u8]] = &[b"opt", b"empty", b"any"];
+
+/// Returns whether `bytes[start..end]` is equal to `expected`.
+const fn range_eq(bytes: &[u8], start: usize, end: usize, expected: &[u8]) -> bool {
+    if end - start != expected.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < expected.len() {
+        if bytes[start + i] != expected[i] {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+/// Returns whether `bytes[start..end]` is equal to any of `candidates`.
+const fn range_eq_any(bytes: &[u8], start: usize, end: usize, candidates: &[&[u8]]) -> bool {
+    let mut i = 0;
+    while i < candidates.len() {
+        if range_eq(bytes, start, end, candidates[i]) {
+            return true;
+        }
+        i += 1;
+    }
+
+    false
+}
+
+/// Returns whether `bytes[start..end]` matches `^[a-zA-Z][a-zA-Z0-9_]*$`.
+const fn is_name(bytes: &[u8], start: usize, end: usize) -> bool {
+    if start == end || !bytes[start].is_ascii_alphabetic() {
+        return false;
+    }
+
+    let mut i = start + 1;
+    while i < end {
+        if !bytes[i].is_ascii_alphanumeric() && bytes[i] != b'_' {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+/// Returns the index of the first `:` or `;` at or after `i`, or the length of `bytes`.
+const fn find_separator(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i] != b':' && bytes[i] != b';' {
+        i += 1;
+    }
+
+    i
+}
+
+/// Checks that the plugin identifier is non-empty and contains no whitespace or NUL characters.
+pub const fn validate_identifier(identifier: &str) -> Result<(), ValidationError> {
+    let bytes = identifier.as_bytes();
+    if bytes.is_empty() {
+        return Err(ValidationError::InvalidIdentifier);
+    }
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == 0 || bytes[i].is_ascii_whitespace() {
+            return Err(ValidationError::InvalidIdentifier);
+        }
+        i += 1;
+    }
+
+    Ok(())
+}
+
+/// Checks that the plugin namespace matches `^[a-z][a-z0-9_]*$`.
+pub const fn validate_namespace(namespace: &str) -> Result<(), ValidationError> {
+    let bytes = namespace.as_bytes();
+    if bytes.is_empty() || !bytes[0].is_ascii_lowercase() {
+        return Err(ValidationError::InvalidNamespace);
+    }
+
+    let mut i = 1;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if !c.is_ascii_lowercase() && !c.is_ascii_digit() && c != b'_' {
+            return Err(ValidationError::InvalidNamespace);
+        }
+        i += 1;
+    }
+
+    Ok(())
+}
+
+/// Checks that the function name matches `^[a-zA-Z][a-zA-Z0-9_]*$`.
+pub const fn validate_function_name(name: &str) -> Result<(), ValidationError> {
+    if is_name(name.as_bytes(), 0, name.len()) {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidFunctionName)
+    }
+}
+
+/// Checks the syntax of an argument or return type string.
+///
+/// See `FilterFunction::args()` for the format.
+pub const fn validate_signature(signature: &str) -> Result<(), ValidationError> {
+    let bytes = signature.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let name_end = find_separator(bytes, i);
+        if !is_name(bytes, i, name_end) {
+            return Err(ValidationError::InvalidArgumentName);
+        }
+
+        if name_end == bytes.len() || bytes[name_end] == b';' {
+            return Err(ValidationError::MissingArgumentType);
+        }
+
+        let type_start = name_end + 1;
+        let mut type_end = find_separator(bytes, type_start);
+        if type_end - type_start >= 2 && range_eq(bytes, type_end - 2, type_end, b"[]") {
+            type_end -= 2;
+        }
+        if !range_eq_any(bytes, type_start, type_end, TYPES) {
+            return Err(ValidationError::InvalidArgumentType);
+        }
+
+        i = find_separator(bytes, type_start);
+        while i < bytes.len() && bytes[i] == b':' {
+            let modifier_end = find_separator(bytes, i + 1);
+            if !range_eq_any(bytes, i + 1, modifier_end, MODIFIERS) {
+                return Err(ValidationError::InvalidArgumentModifier);
+            }
+            i = modifier_end;
+        }
+
+        // Skip the semicolon, the last one is optional.
+        i += 1;
+    }
+
+    Ok(())
+}
+
+impl Metadata {
+    /// Checks the identifier, namespace and name of the plugin.
+    pub const fn validate(&self) -> Result<(), ValidationError> {
+        if let Err(e) = validate_identifier(self.identifier) {
+            return Err(e);
+        }
+
+        if let Err(e) = validate_namespace(self.namespace) {
+            return Err(e);
+        }
+
+        let name = self.name.as_bytes();
+        if name.is_empty() {
+            return Err(ValidationError::InvalidPluginName);
+        }
+
+        let mut i = 0;
+        while i < name.len() {
+            if name[i] == 0 {
+                return Err(ValidationError::InvalidPluginName);
+            }
+            i += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Panics with the error message if the validation failed.
+///
+/// This is used by the macros to turn validation errors into compile errors.
+#[doc(hidden)]
+#[track_caller]
+pub const fn assert_valid(result: Result<(), ValidationError>) {
+    if let Err(e) = result {
+        panic!("{}", e.message());
+    }
+}
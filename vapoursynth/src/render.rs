@@ -0,0 +1,275 @@
+//! Rendering ranges of frames.
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::core::CoreRef;
+use crate::frame::FrameRef;
+use crate::node::{GetFrameError, Node};
+
+/// The error type for `Render::run()`.
+#[derive(Error, Debug)]
+pub enum RenderError {
+    #[error("Couldn't get frame {n}")]
+    GetFrame {
+        n: usize,
+        #[source]
+        source: GetFrameError<'static>,
+    },
+    #[error("Couldn't output frame {n}")]
+    Sink {
+        n: usize,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("The render was cancelled")]
+    Cancelled,
+}
+
+/// A token for cancelling a render from another thread.
+///
+/// Clones of a token refer to the same cancellation flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a new token which isn't cancelled.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the renders using this token.
+    ///
+    /// The renders stop before outputting the next frame.
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the token was cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The progress of a render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// Number of frames which have been output.
+    pub frames_done: usize,
+
+    /// Total number of frames to output.
+    pub total_frames: usize,
+
+    /// Time since the start of the render.
+    pub elapsed: Duration,
+
+    /// Average number of frames output per second.
+    pub fps: f64,
+
+    /// Estimated time until the end of the render, if it can be estimated.
+    pub eta: Option<Duration>,
+
+    /// Current number of concurrent frame requests.
+    pub requests: usize,
+}
+
+impl Progress {
+    fn new(frames_done: usize, total_frames: usize, elapsed: Duration, requests: usize) -> Self {
+        let seconds = elapsed.as_secs_f64();
+        let fps = if seconds > 0f64 {
+            frames_done as f64 / seconds
+        } else {
+            0f64
+        };
+
+        let eta = if fps > 0f64 {
+            Some(Duration::from_secs_f64(
+                (total_frames - frames_done) as f64 / fps,
+            ))
+        } else {
+            None
+        };
+
+        Self {
+            frames_done,
+            total_frames,
+            elapsed,
+            fps,
+            eta,
+            requests,
+        }
+    }
+}
+
+/// A function called with the progress of a render.
+type ProgressCallback<'a> = Box<dyn FnMut(&Progress) + 'a>;
+
+/// A job outputting a range of frames of a node to a sink.
+///
+/// Frames are requested concurrently and passed to the sink in order. The number of concurrent
+/// requests adapts to the memory usage of the core: it's halved when the framebuffer cache grows
+/// past a fraction of its maximal size (see `Info::max_framebuffer_size`) and increased back one
+/// request at a time otherwise. The frames requested before halving still use memory, so it isn't
+/// halved again until they have been output.
+///
+/// # Example
+/// ```no_run
+/// # use vapoursynth::prelude::*;
+/// # use vapoursynth::core::CoreRef;
+/// use vapoursynth::render::{Render, RenderError};
+///
+/// # fn foo(core: CoreRef, node: &Node) -> Result<(), RenderError> {
+/// let mut total_width = 0;
+///
+/// Render::new(core, node, 0..node.info().num_frames)
+///     .on_progress(|progress| eprint!("{}/{}\r", progress.frames_done, progress.total_frames))
+///     .run(|_n, frame| {
+///         total_width += frame.width(0);
+///         Ok(())
+///     })?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Render<'core, 'a> {
+    core: CoreRef<'core>,
+    node: Node<'core>,
+    range: Range<usize>,
+    requests: usize,
+    framebuffer_threshold: f64,
+    cancellation_token: Option<CancellationToken>,
+    on_progress: Option<ProgressCallback<'a>>,
+}
+
+impl<'core, 'a> Render<'core, 'a> {
+    /// Creates a new render of `range` of frames of `node`.
+    ///
+    /// By default the number of concurrent requests is up to the number of worker threads of
+    /// `core`, and it's lowered when the framebuffer cache exceeds 90% of its maximal size.
+    pub fn new(core: CoreRef<'core>, node: &Node<'core>, range: Range<usize>) -> Self {
+        Self {
+            core,
+            node: node.clone(),
+            range,
+            requests: core.info().num_threads.max(1),
+            framebuffer_threshold: 0.9,
+            cancellation_token: None,
+            on_progress: None,
+        }
+    }
+
+    /// Sets the maximal number of concurrent frame requests.
+    ///
+    /// # Panics
+    /// Panics if `requests` is zero.
+    #[inline]
+    pub fn requests(mut self, requests: usize) -> Self {
+        assert!(requests > 0, "requests must be greater than zero");
+        self.requests = requests;
+        self
+    }
+
+    /// Sets the fraction of `Info::max_framebuffer_size` above which the number of concurrent
+    /// requests is lowered.
+    ///
+    /// # Panics
+    /// Panics if `threshold` isn't in `0.0..=1.0`.
+    #[inline]
+    pub fn framebuffer_threshold(mut self, threshold: f64) -> Self {
+        assert!(
+            (0f64..=1f64).contains(&threshold),
+            "threshold must be in 0.0..=1.0"
+        );
+        self.framebuffer_threshold = threshold;
+        self
+    }
+
+    /// Sets the token for cancelling the render.
+    #[inline]
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Sets the function called after every output frame.
+    #[inline]
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&Progress) + 'a,
+    {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Returns whether the framebuffer cache is above the threshold.
+    fn is_memory_constrained(&self) -> bool {
+        let info = self.core.info();
+        info.max_framebuffer_size > 0
+            && info.used_framebuffer_size as f64
+                >= info.max_framebuffer_size as f64 * self.framebuffer_threshold
+    }
+
+    /// Runs the render, passing the frames and their numbers to `sink` in order.
+    ///
+    /// Blocks until all frames have been output, an error occurs or the render is cancelled.
+    /// Returns the final progress. When stopping early, this also waits for the frames which were
+    /// already requested.
+    pub fn run<F>(mut self, mut sink: F) -> Result<Progress, RenderError>
+    where
+        F: FnMut(usize, FrameRef<'core>) -> Result<(), anyhow::Error>,
+    {
+        let start_time = Instant::now();
+        let total_frames = self.range.len();
+
+        let mut frames = self.node.frames(self.range.clone(), self.requests);
+        let mut progress = Progress::new(0, total_frames, Duration::ZERO, self.requests);
+
+        // Number of frames to output before the number of requests can be lowered again.
+        let mut cooldown = 0;
+
+        for n in self.range.clone() {
+            if let Some(ref token) = self.cancellation_token
+                && token.is_cancelled()
+            {
+                return Err(RenderError::Cancelled);
+            }
+
+            // Additive increase, multiplicative decrease.
+            let max_in_flight = frames.max_in_flight();
+            let requests = if !self.is_memory_constrained() {
+                (max_in_flight + 1).min(self.requests)
+            } else if cooldown == 0 {
+                cooldown = max_in_flight;
+                (max_in_flight / 2).max(1)
+            } else {
+                max_in_flight
+            };
+            cooldown = cooldown.saturating_sub(1);
+            frames.set_max_in_flight(requests);
+
+            let frame = frames
+                .next()
+                .unwrap()
+                .map_err(|source| RenderError::GetFrame { n, source })?;
+
+            sink(n, frame).map_err(|source| RenderError::Sink { n, source })?;
+
+            progress = Progress::new(
+                n - self.range.start + 1,
+                total_frames,
+                start_time.elapsed(),
+                requests,
+            );
+            if let Some(ref mut on_progress) = self.on_progress {
+                on_progress(&progress);
+            }
+        }
+
+        Ok(progress)
+    }
+}
@@ -303,6 +303,36 @@ mod need_api_and_vsscript {
         );
     }
 
+    #[test]
+    fn outputs() {
+        let env = vsscript::Environment::from_file(
+            "test-vpy/pixel-formats.vpy",
+            vsscript::EvalFlags::Nothing,
+        )
+        .unwrap();
+
+        let outputs = env.outputs().unwrap();
+        assert_eq!(
+            outputs.iter().map(|x| x.index).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+
+        for output in &outputs {
+            assert!(output.alpha_node.is_none());
+            assert_eq!(output.alt_output_mode, 0);
+            assert_eq!(output.node.info().num_frames, 100);
+        }
+
+        let env =
+            vsscript::Environment::from_file("test-vpy/alpha.vpy", vsscript::EvalFlags::Nothing)
+                .unwrap();
+
+        let outputs = env.outputs().unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].index, 0);
+        assert!(outputs[0].alpha_node.is_some());
+    }
+
     #[test]
     #[should_panic]
     fn invalid_component_type() {
@@ -349,12 +379,105 @@ mod need_api_and_vsscript {
 
     #[test]
     fn clear_output() {
-        let env =
+        let mut env =
             vsscript::Environment::from_script(include_str!("../test-vpy/green.vpy")).unwrap();
-        // In v4, clear_output is a no-op that always succeeds
-        assert!(env.clear_output(1).is_ok());
-        assert!(env.clear_output(0).is_ok());
+        assert!(matches!(
+            env.clear_output(1),
+            Err(vsscript::Error::NoOutput)
+        ));
         assert!(env.clear_output(0).is_ok());
+        assert!(matches!(
+            env.clear_output(0),
+            Err(vsscript::Error::NoOutput)
+        ));
+        assert!(env.get_output(0).is_err());
+    }
+
+    #[test]
+    fn clear() {
+        let mut env =
+            vsscript::Environment::from_script(include_str!("../test-vpy/green.vpy")).unwrap();
+        let (node, _) = env.get_output(0).unwrap();
+        drop(node);
+
+        assert!(env.clear().is_ok());
+        assert!(env.get_output(0).is_err());
+
+        let mut map = OwnedMap::new(API::get().unwrap());
+        assert!(env.get_variable("video", &mut map).is_err());
+
+        // The environment is still usable.
+        env.eval_script(include_str!("../test-vpy/green.vpy"))
+            .unwrap();
+        green_test(&env);
+    }
+
+    #[test]
+    fn environment_builder() {
+        use std::sync::{Arc, Mutex};
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let messages_2 = messages.clone();
+
+        let mut env = vsscript::Environment::builder()
+            .thread_count(2)
+            .max_cache_size(256 << 20)
+            .script_filename("builder.vpy")
+            .log_handler(move |message_type, message| {
+                messages_2
+                    .lock()
+                    .unwrap()
+                    .push((message_type, message.to_owned()));
+            })
+            .build()
+            .unwrap();
+
+        env.eval_script(
+            "import vapoursynth as vs\n\
+             vs.core.log_message(vs.MESSAGE_TYPE_WARNING, 'log handler test')",
+        )
+        .unwrap();
+        assert!(
+            messages
+                .lock()
+                .unwrap()
+                .contains(&(MessageType::Warning, "log handler test".to_owned()))
+        );
+
+        let info = env.get_core().unwrap().info();
+        assert_eq!(info.num_threads, 2);
+        assert_eq!(info.max_framebuffer_size, 256 << 20);
+
+        let err = env.eval_script("x = undefined_name").unwrap_err();
+        let vsscript::Error::VSScript(err) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(
+            err.exception().unwrap().file.as_deref(),
+            Some("builder.vpy")
+        );
+
+        env.eval_script(include_str!("../test-vpy/green.vpy"))
+            .unwrap();
+        green_test(&env);
+    }
+
+    #[test]
+    fn script_errors() {
+        let err = vsscript::Environment::from_script("x = undefined_name").unwrap_err();
+        let vsscript::Error::VSScript(err) = err else {
+            panic!("unexpected error: {err}");
+        };
+        let exception = err.exception().unwrap();
+        assert_eq!(exception.kind, "NameError");
+        assert_eq!(exception.file.as_deref(), Some("<string>"));
+        assert_eq!(exception.line, Some(1));
+
+        let err = vsscript::Environment::from_script("import sys\nsys.exit(3)").unwrap_err();
+        let vsscript::Error::VSScript(err) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(err.exit_code(), 3);
     }
 
     #[test]
@@ -375,19 +498,77 @@ mod need_api_and_vsscript {
 
     #[test]
     fn vsscript_variables() {
-        let env =
+        let mut env =
             vsscript::Environment::from_script(include_str!("../test-vpy/green.vpy")).unwrap();
 
         let mut map = OwnedMap::new(API::get().unwrap());
         assert!(env.get_variable("video", &mut map).is_ok());
-        // In v4, clear_variable is a no-op that always succeeds
-        assert!(env.clear_variable("video").is_ok());
         assert!(env.clear_variable("video").is_ok());
+        assert!(matches!(
+            env.clear_variable("video"),
+            Err(vsscript::Error::NoSuchVariable)
+        ));
+        assert!(matches!(
+            env.clear_variable("it's not an identifier"),
+            Err(vsscript::Error::NoSuchVariable)
+        ));
+
+        let mut other_map = OwnedMap::new(API::get().unwrap());
+        assert!(env.get_variable("video", &mut other_map).is_err());
 
         assert!(env.set_variables(&map).is_ok());
         assert!(env.get_variable("video", &mut map).is_ok());
     }
 
+    #[test]
+    fn map_owned_setters() {
+        let env =
+            vsscript::Environment::from_script(include_str!("../test-vpy/green.vpy")).unwrap();
+        let (node, _) = env.get_output(0).unwrap();
+        let frame = node.get_frame(0).unwrap();
+
+        let mut map = OwnedMap::new(API::get().unwrap());
+        assert_eq!(map.set_node_owned("clip", node.clone()), Ok(()));
+        assert_eq!(map.append_node_owned("clip", node.clone()), Ok(()));
+        assert_eq!(map.value_count("clip"), Ok(2));
+        assert_eq!(map.get_video_node("clip").unwrap().info().num_frames, 100);
+
+        assert_eq!(map.set_frame_owned("frame", frame.clone()), Ok(()));
+        assert_eq!(map.append_frame_owned("frame", frame), Ok(()));
+        assert_eq!(map.value_count("frame"), Ok(2));
+
+        // The node is consumed even if appending fails.
+        assert_eq!(
+            map.append_node_owned("frame", node),
+            Err(map::Error::WrongValueType)
+        );
+    }
+
+    #[test]
+    fn vsscript_typed_variables() {
+        let mut env =
+            vsscript::Environment::from_script(include_str!("../test-vpy/green.vpy")).unwrap();
+
+        {
+            let node = env.get::<Node>("video").unwrap();
+            assert_eq!(node.info().num_frames, 100);
+            env.set("clip", &node).unwrap();
+        }
+        assert!(matches!(
+            env.get::<i64>("video"),
+            Err(vsscript::Error::Map(map::Error::WrongValueType))
+        ));
+        assert!(matches!(
+            env.get::<i64>("missing"),
+            Err(vsscript::Error::NoSuchVariable)
+        ));
+
+        env.set("length", &42i64).unwrap();
+        env.eval_script("result = clip.num_frames + length\n")
+            .unwrap();
+        assert_eq!(env.get::<i64>("result").unwrap(), 142);
+    }
+
     #[test]
     fn get_frame_async() {
         let env =
@@ -435,6 +616,267 @@ mod need_api_and_vsscript {
         }
     }
 
+    /// Runs a future to completion on the current thread.
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+        use std::thread::{self, Thread};
+
+        struct ThreadWaker(Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn get_frame_future() {
+        let env =
+            vsscript::Environment::from_file("test-vpy/green.vpy", vsscript::EvalFlags::Nothing)
+                .unwrap();
+
+        let node = env.get_output(0).unwrap().0;
+
+        let futures = (0..10)
+            .map(|n| node.get_frame_future(n))
+            .collect::<Vec<_>>();
+        for future in futures {
+            let frame = block_on(future).unwrap();
+            green_frame_test(&frame);
+            props_test(&frame, 60);
+        }
+
+        // The clip only has 100 frames, so requesting the 101th one produces an error.
+        let frame = block_on(async { node.get_frame_future(100).await });
+        assert!(frame.is_err());
+        drop(frame);
+
+        // Dropping the future before the frame is ready waits for the frame, so the environment
+        // can be freed right away.
+        drop(node.get_frame_future(0));
+        drop(node);
+        drop(env);
+    }
+
+    #[test]
+    fn frames() {
+        let env =
+            vsscript::Environment::from_file("test-vpy/green.vpy", vsscript::EvalFlags::Nothing)
+                .unwrap();
+
+        let node = env.get_output(0).unwrap().0;
+
+        let mut count = 0;
+        for frame in node.frames(10..60, 4) {
+            let frame = frame.unwrap();
+            green_frame_test(&frame);
+            props_test(&frame, 60);
+            count += 1;
+        }
+        assert_eq!(count, 50);
+
+        // The clip only has 100 frames, so the iteration stops with an error at frame 100.
+        let mut frames = node.frames(95..105, 8);
+        for _ in 95..100 {
+            assert!(frames.next().unwrap().is_ok());
+        }
+        assert!(frames.next().unwrap().is_err());
+        assert!(frames.next().is_none());
+        drop(frames);
+
+        // Dropping the iterator with frames in flight is fine.
+        let mut frames = node.frames(0..100, 8);
+        assert!(frames.next().unwrap().is_ok());
+        drop(frames);
+
+        // Dropping waits for the frames in flight, so the environment can be freed right away.
+        drop(node);
+        drop(env);
+    }
+
+    #[test]
+    fn shared_environment() {
+        use std::thread;
+
+        let env =
+            vsscript::Environment::from_file("test-vpy/green.vpy", vsscript::EvalFlags::Nothing)
+                .unwrap();
+        let env = vsscript::SharedEnvironment::new(env);
+
+        let core = env.get_core().unwrap();
+        let (node, alpha_node) = env.get_output(0).unwrap();
+        assert!(alpha_node.is_none());
+        assert_eq!(
+            core.core().info().num_threads,
+            env.get_core().unwrap().core().info().num_threads
+        );
+
+        // The handles keep the environment alive.
+        let env = env.try_unwrap().unwrap_err();
+        drop(env);
+        drop(core);
+
+        let frame = thread::spawn(move || {
+            let frame = node.get_frame(0).unwrap();
+            green_frame_test(frame.frame());
+
+            let (tx, rx) = channel();
+            node.get_frame_async(1, move |frame, n, node| {
+                assert_eq!(n, 1);
+                assert_eq!(node.node().info().num_frames, 100);
+                tx.send(frame.unwrap()).unwrap();
+            });
+            let frame = rx.recv().unwrap();
+            green_frame_test(frame.frame());
+
+            frame
+        })
+        .join()
+        .unwrap();
+
+        // The last handle is the frame. The callback above may still be returning, but
+        // try_unwrap() waits for it.
+        let env = frame.environment().clone();
+        drop(frame);
+        assert!(env.try_unwrap().is_ok());
+
+        // The callback drops the last handle, which frees the environment on another thread.
+        let env =
+            vsscript::Environment::from_file("test-vpy/green.vpy", vsscript::EvalFlags::Nothing)
+                .unwrap();
+        let node = vsscript::SharedEnvironment::new(env)
+            .get_output(0)
+            .unwrap()
+            .0;
+
+        let (tx, rx) = channel();
+        node.get_frame_async(0, move |frame, _, _| {
+            tx.send(frame.is_ok()).unwrap();
+        });
+        drop(node);
+        assert!(rx.recv().unwrap());
+    }
+
+    #[test]
+    fn scope() {
+        use std::sync::Mutex;
+
+        let env =
+            vsscript::Environment::from_file("test-vpy/green.vpy", vsscript::EvalFlags::Nothing)
+                .unwrap();
+
+        let core = env.get_core().unwrap();
+        let node = env.get_output(0).unwrap().0;
+
+        let frames = Mutex::new(Vec::new());
+        let errors = Mutex::new(0);
+
+        let result = core.scope(|s| {
+            for n in 0..10 {
+                s.get_frame_async(&node, n, |frame, n, node| {
+                    green_frame_test(&frame.unwrap());
+                    frames.lock().unwrap().push(n);
+
+                    // Requests from within callbacks are waited for too.
+                    if n == 9 {
+                        s.get_frame_async(&node, 100, |frame, _, _| {
+                            assert!(frame.is_err());
+                            *errors.lock().unwrap() += 1;
+                        });
+                    }
+                });
+            }
+
+            42
+        });
+        assert_eq!(result, 42);
+
+        // All callbacks have finished when the scope returns.
+        let mut frames = frames.into_inner().unwrap();
+        frames.sort_unstable();
+        assert_eq!(frames, (0..10).collect::<Vec<_>>());
+        assert_eq!(errors.into_inner().unwrap(), 1);
+    }
+
+    #[test]
+    fn render() {
+        use crate::render::{CancellationToken, Render, RenderError};
+
+        let env =
+            vsscript::Environment::from_file("test-vpy/green.vpy", vsscript::EvalFlags::Nothing)
+                .unwrap();
+
+        let core = env.get_core().unwrap();
+        let node = env.get_output(0).unwrap().0;
+
+        let mut next_frame = 10;
+        let mut progress_calls = 0;
+        let progress = Render::new(core, &node, 10..60)
+            .requests(4)
+            .on_progress(|progress| {
+                progress_calls += 1;
+                assert_eq!(progress.frames_done, progress_calls);
+                assert_eq!(progress.total_frames, 50);
+                assert!(progress.requests >= 1 && progress.requests <= 4);
+            })
+            .run(|n, frame| {
+                assert_eq!(n, next_frame);
+                next_frame += 1;
+                green_frame_test(&frame);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(next_frame, 60);
+        assert_eq!(progress.frames_done, 50);
+        assert_eq!(progress.eta, Some(std::time::Duration::ZERO));
+
+        // The clip only has 100 frames.
+        match Render::new(core, &node, 95..105).run(|_, _| Ok(())) {
+            Err(RenderError::GetFrame { n: 100, .. }) => {}
+            x => panic!("unexpected result: {:?}", x),
+        }
+
+        // Sink errors stop the render.
+        match Render::new(core, &node, 0..100).run(|n, _| {
+            if n == 5 {
+                Err(anyhow::anyhow!("sink error"))
+            } else {
+                Ok(())
+            }
+        }) {
+            Err(RenderError::Sink { n: 5, .. }) => {}
+            x => panic!("unexpected result: {:?}", x),
+        }
+
+        // Cancelling stops the render before the next frame.
+        let token = CancellationToken::new();
+        let mut frames_done = 0;
+        let result = Render::new(core, &node, 0..100)
+            .cancellation_token(token.clone())
+            .run(|n, _| {
+                frames_done += 1;
+                if n == 9 {
+                    token.cancel();
+                }
+                Ok(())
+            });
+        assert!(matches!(result, Err(RenderError::Cancelled)));
+        assert_eq!(frames_done, 10);
+    }
+
     #[test]
     fn get_frame_async_error() {
         let env =
@@ -469,6 +911,94 @@ mod need_api_and_vsscript {
         assert_eq!(rx.recv(), Ok(()));
     }
 
+    #[test]
+    fn frame_props() {
+        use frame_props::*;
+
+        let env =
+            vsscript::Environment::from_script(include_str!("../test-vpy/green.vpy")).unwrap();
+        let core = env.get_core().unwrap();
+        let node = env.get_output(0).unwrap().0;
+        let frame = node.get_frame(0).unwrap();
+
+        assert_eq!(
+            frame.duration(),
+            Ok(Some(Rational {
+                numerator: 1,
+                denominator: 60,
+            }))
+        );
+        assert_eq!(frame.matrix(), Ok(None));
+        assert_eq!(frame.pict_type(), Ok(None));
+        assert_eq!(frame.combed(), Ok(None));
+
+        let mut frame = FrameRefMut::copy_of(core, &frame);
+        assert_eq!(frame.set_matrix(MatrixCoefficients::BT709), Ok(()));
+        assert_eq!(frame.matrix(), Ok(Some(MatrixCoefficients::BT709)));
+        assert_eq!(frame.set_color_range(ColorRange::Limited), Ok(()));
+        assert_eq!(frame.color_range(), Ok(Some(ColorRange::Limited)));
+        assert_eq!(frame.set_field_based(FieldBased::TopFieldFirst), Ok(()));
+        assert_eq!(frame.field_based(), Ok(Some(FieldBased::TopFieldFirst)));
+        assert_eq!(frame.set_pict_type(PictType::B), Ok(()));
+        assert_eq!(frame.pict_type(), Ok(Some(PictType::B)));
+        assert_eq!(frame.set_pict_type(PictType::Other(b'S')), Ok(()));
+        assert_eq!(frame.pict_type(), Ok(Some(PictType::Other(b'S'))));
+        assert_eq!(frame.set_scene_change_next(true), Ok(()));
+        assert_eq!(frame.scene_change_next(), Ok(Some(true)));
+        assert_eq!(frame.scene_change_prev(), Ok(None));
+
+        let sar = Rational {
+            numerator: 32,
+            denominator: 27,
+        };
+        assert_eq!(frame.set_sample_aspect_ratio(sar), Ok(()));
+        assert_eq!(frame.sample_aspect_ratio(), Ok(Some(sar)));
+        assert_eq!(
+            frame.set_duration(Rational {
+                numerator: 1,
+                denominator: 0,
+            }),
+            Err(Error::InvalidValue {
+                key: DURATION_DEN,
+                value: 0,
+            })
+        );
+
+        frame.props_mut().set_int(MATRIX, 3).unwrap();
+        assert_eq!(
+            frame.matrix(),
+            Err(Error::InvalidValue {
+                key: MATRIX,
+                value: 3,
+            })
+        );
+        frame.props_mut().set_int(COMBED, 2).unwrap();
+        assert_eq!(
+            frame.combed(),
+            Err(Error::InvalidValue {
+                key: COMBED,
+                value: 2,
+            })
+        );
+        frame.props_mut().set_data(PICT_TYPE, b"X").unwrap();
+        assert_eq!(frame.pict_type(), Ok(Some(PictType::Other(b'X'))));
+        frame.props_mut().set_data(PICT_TYPE, b"XY").unwrap();
+        assert_eq!(
+            frame.pict_type(),
+            Err(Error::InvalidData { key: PICT_TYPE })
+        );
+        frame.props_mut().set_data(PICT_TYPE, b"").unwrap();
+        assert_eq!(
+            frame.pict_type(),
+            Err(Error::InvalidData { key: PICT_TYPE })
+        );
+        frame.props_mut().set_float(TRANSFER, 1.0).unwrap();
+        assert_eq!(
+            frame.transfer(),
+            Err(Error::Map(map::Error::WrongValueType))
+        );
+    }
+
     #[test]
     fn core() {
         let env =
@@ -526,6 +1056,112 @@ mod need_api_and_vsscript {
         assert!(out.error().is_none());
         assert_eq!(out.get_int("there").unwrap(), 42);
     }
+
+    #[test]
+    fn function_panics() {
+        let env =
+            vsscript::Environment::from_file("test-vpy/green.vpy", vsscript::EvalFlags::Nothing)
+                .unwrap();
+
+        let core = env.get_core().unwrap();
+        let api = API::get().unwrap();
+
+        let function = Function::new(api, core, |_api, _core, in_, _out| {
+            if in_.get_int("panic").is_ok() {
+                panic!("{} requested", "panic");
+            }
+        });
+
+        let mut in_ = OwnedMap::new(api);
+        let mut out = OwnedMap::new(api);
+        in_.set_int("panic", 1).unwrap();
+
+        function.call(&in_, &mut out);
+        assert_eq!(
+            out.error().as_deref(),
+            Some("The function panicked: panic requested")
+        );
+
+        // The function can still be called afterwards.
+        in_.clear();
+        out.clear();
+        function.call(&in_, &mut out);
+        assert_eq!(out.error(), None);
+    }
+
+    #[test]
+    fn typed_functions() {
+        let env =
+            vsscript::Environment::from_file("test-vpy/green.vpy", vsscript::EvalFlags::Nothing)
+                .unwrap();
+
+        let core = env.get_core().unwrap();
+        let node = env.get_output(0).unwrap().0;
+
+        let add = Function::typed(
+            core,
+            ["a", "b"],
+            |a: i64, b: Option<i64>| -> anyhow::Result<i64> { Ok(a + b.unwrap_or(0)) },
+        );
+        assert_eq!(
+            add.call_typed::<_, i64, 2>(["a", "b"], (40, 2)).unwrap(),
+            42
+        );
+        assert_eq!(add.call_typed::<_, i64, 1>(["a"], (40,)).unwrap(), 40);
+        assert!(matches!(
+            add.call_typed::<_, i64, 1>(["b"], (2,)),
+            Err(function::Error::Call(message)) if message == "Argument a is required"
+        ));
+        assert!(matches!(
+            add.call_typed::<_, String, 1>(["a"], (1,)),
+            Err(function::Error::ReturnValue(plugins::ArgumentError::Map {
+                key: "val",
+                ..
+            }))
+        ));
+
+        let scale = Function::typed(
+            core,
+            ["x", "double"],
+            |x: u32, double: bool| -> anyhow::Result<i64> {
+                Ok(if double { x as i64 * 2 } else { x as i64 })
+            },
+        );
+        assert_eq!(
+            scale
+                .call_typed::<_, i64, 2>(["x", "double"], (21, true))
+                .unwrap(),
+            42
+        );
+        assert!(matches!(
+            scale.call_typed::<_, i64, 2>(["x", "double"], (-1, false)),
+            Err(function::Error::Call(message))
+                if message == "Argument x doesn't fit into u32: -1"
+        ));
+
+        let fail = Function::typed(core, [], || -> anyhow::Result<()> {
+            Err(anyhow::anyhow!("failed"))
+        });
+        assert!(matches!(
+            fail.call_typed::<_, (), 0>([], ()),
+            Err(function::Error::Call(message)) if message == "failed"
+        ));
+
+        let clip = node.clone();
+        let eval = Function::typed(core, ["n"], move |n: i64| -> anyhow::Result<Node> {
+            assert!(n >= 0);
+            Ok(clip.clone())
+        });
+        let std = core
+            .get_plugin_by_id("com.vapoursynth.std")
+            .unwrap()
+            .unwrap();
+        let args = crate::map! { "clip" => &node, "eval" => &eval }.unwrap();
+        let rv = std.invoke("FrameEval", &args).unwrap();
+        assert_eq!(rv.error(), None);
+        let evaluated = rv.get_video_node("clip").unwrap();
+        green_frame_test(&evaluated.get_frame(0).unwrap());
+    }
 }
 
 // We need either VSScript or the VapourSynth functions.
@@ -627,6 +1263,102 @@ mod need_api {
             assert_eq!(iter.next(), None);
         }
 
+        assert_eq!(map.data_type_hint("d"), Ok(DataTypeHint::Unknown));
+
+        assert_eq!(map.set_str("s", "hello"), Ok(()));
+        assert_eq!(map.get_str("s"), Ok("hello"));
+        assert_eq!(map.data_type_hint("s"), Ok(DataTypeHint::Utf8));
+        assert_eq!(map.append_str("s", "wörld"), Ok(()));
+        {
+            let iter = map.get_str_iter("s");
+            assert!(iter.is_ok());
+            let mut iter = iter.unwrap();
+            assert_eq!(iter.next(), Some("hello"));
+            assert_eq!(iter.next(), Some("wörld"));
+            assert_eq!(iter.next(), None);
+        }
+
+        assert_eq!(map.set("s", &"hello".to_owned()), Ok(()));
+        assert_eq!(map.get("s"), Ok("hello".to_owned()));
+        assert_eq!(map.append("s", &"wörld"), Ok(()));
+        assert_eq!(map.get("s"), Ok("hello"));
+        {
+            let iter = map.get_iter::<String>("s");
+            assert!(iter.is_ok());
+            let mut iter = iter.unwrap();
+            assert_eq!(iter.next(), Some("hello".to_owned()));
+            assert_eq!(iter.next(), Some("wörld".to_owned()));
+            assert_eq!(iter.next(), None);
+        }
+
+        assert_eq!(map.append_data("s", &[0xff]), Ok(()));
+        assert!(matches!(
+            map.get_str_iter("s"),
+            Err(map::Error::InvalidUtf8(_))
+        ));
+        assert_eq!(map.get_str("i"), Err(map::Error::WrongValueType));
+        assert_eq!(map.data_type_hint("i"), Err(map::Error::WrongValueType));
+
+        assert_eq!(map.set_int("big", i64::MAX), Ok(()));
+        assert_eq!(map.get_i32("big"), Ok(i32::MAX));
+        assert_eq!(map.set_float("big_f", f64::MAX), Ok(()));
+        assert_eq!(map.get_f32("big_f"), Ok(f32::MAX));
+        assert_eq!(map.get_i32("big_f"), Err(map::Error::WrongValueType));
+
+        assert_eq!(map.set_empty("e", ValueType::VideoNode), Ok(()));
+        assert_eq!(map.value_type("e"), Ok(ValueType::VideoNode));
+        assert_eq!(map.value_count("e"), Ok(0));
+        assert_eq!(map.set_empty("big", ValueType::Float), Ok(()));
+        assert_eq!(map.value_type("big"), Ok(ValueType::Float));
+        assert_eq!(map.value_count("big"), Ok(0));
+
+        {
+            let mut other = OwnedMap::new(API::get().unwrap());
+            assert_eq!(other.set_int("big_f", 1), Ok(()));
+            assert_eq!(other.set_int("other", 2), Ok(()));
+
+            let mut merged = map.clone();
+            assert_eq!(merged.key_count(), map.key_count());
+            merged.merge_from(&other);
+            assert_eq!(merged.key_count(), map.key_count() + 1);
+            assert_eq!(merged.get_int("big_f"), Ok(1));
+            assert_eq!(merged.get_int("other"), Ok(2));
+            assert_eq!(map.get_f32("big_f"), Ok(f32::MAX));
+        }
+
+        {
+            let mut map = OwnedMap::new(API::get().unwrap());
+            assert_eq!(map.set_int_array("i", &[1, 2]), Ok(()));
+            assert_eq!(map.set_float("f", 0.5), Ok(()));
+            assert_eq!(map.set_data("d", &[1, 2, 3]), Ok(()));
+            assert_eq!(map.set_str("s", "hi"), Ok(()));
+            assert_eq!(map.set_empty("e", ValueType::Int), Ok(()));
+
+            assert_eq!(map.get_any("i"), Ok(MapValue::Int(&[1, 2])));
+            assert_eq!(map.get_any("f"), Ok(MapValue::Float(&[0.5])));
+            assert_eq!(map.get_any("d"), Ok(MapValue::Data(vec![&[1, 2, 3][..]])));
+            assert_eq!(map.get_any("s"), Ok(MapValue::Str(vec!["hi"])));
+            assert_eq!(map.get_any("e"), Ok(MapValue::Int(&[])));
+            assert_eq!(map.get_any("x"), Err(map::Error::KeyNotFound));
+
+            let entries = map.iter().collect::<Vec<_>>();
+            assert_eq!(entries.len(), 5);
+            assert!(entries.contains(&("s", MapValue::Str(vec!["hi"]))));
+
+            let debug = format!("{map:?}");
+            assert!(debug.contains(r#""i": Int([1, 2])"#));
+            assert!(debug.contains(r#""s": Str(["hi"])"#));
+
+            let mut other = map.clone();
+            assert_eq!(map, other);
+            assert_eq!(other.append_int("i", 3), Ok(()));
+            assert_ne!(map, other);
+            assert_eq!(other.set_int_array("i", &[1, 2]), Ok(()));
+            assert_eq!(map, other);
+            assert_eq!(other.set_data("s", b"hi"), Ok(()));
+            assert_ne!(map, other);
+        }
+
         // TODO: node, frame and function method tests when we can make them.
 
         assert_eq!(map.delete_key("i"), Ok(()));
@@ -640,6 +1372,315 @@ mod need_api {
         );
     }
 
+    #[cfg(feature = "macros")]
+    #[test]
+    fn derive_frame_props() {
+        use frame_props::{self, FrameProps, PictType};
+
+        #[derive(FrameProps, Debug, PartialEq)]
+        struct Props {
+            #[frame_props(rename = "PlaneStatsAverage")]
+            average: f64,
+            #[frame_props(rename = "_PictType")]
+            pict_type: PictType,
+            width: u16,
+            scene: Option<bool>,
+            planes: Vec<i64>,
+            names: Vec<String>,
+            r#type: String,
+        }
+
+        let props = Props {
+            average: 0.5,
+            pict_type: PictType::P,
+            width: 1920,
+            scene: None,
+            planes: vec![0, 2],
+            names: vec!["Y".to_owned(), "V".to_owned()],
+            r#type: "luma".to_owned(),
+        };
+
+        let mut map = OwnedMap::new(API::get().unwrap());
+        assert_eq!(props.write_to(&mut map), Ok(()));
+        assert_eq!(map.get_float("PlaneStatsAverage"), Ok(0.5));
+        assert_eq!(map.get_str("_PictType"), Ok("P"));
+        assert_eq!(map.get_str("type"), Ok("luma"));
+        assert_eq!(map.value_count("scene"), Err(map::Error::KeyNotFound));
+        assert_eq!(Props::read_from(&map), Ok(props));
+
+        assert_eq!(map.set_int("width", 65536), Ok(()));
+        assert_eq!(
+            Props::read_from(&map),
+            Err(frame_props::Error::InvalidValue {
+                key: "width",
+                value: 65536,
+            })
+        );
+
+        assert_eq!(map.set_int("width", 1280), Ok(()));
+        assert_eq!(map.set_float("scene", 1.0), Ok(()));
+        assert_eq!(
+            Props::read_from(&map),
+            Err(frame_props::Error::Property {
+                key: "scene",
+                source: map::Error::WrongValueType,
+            })
+        );
+
+        assert_eq!(map.delete_key("planes"), Ok(()));
+        assert_eq!(map.set_int("scene", 1), Ok(()));
+        assert_eq!(
+            Props::read_from(&map),
+            Err(frame_props::Error::Property {
+                key: "planes",
+                source: map::Error::KeyNotFound,
+            })
+        );
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn derive_filter_args() {
+        use plugins::{ArgumentError, FilterArgs};
+
+        #[derive(FilterArgs, Debug, PartialEq)]
+        struct Args {
+            #[filter_args(range = 1..=100)]
+            radius: i64,
+            #[filter_args(default = 0.5, range = 0.0..=1.0)]
+            strength: f64,
+            #[filter_args(rename = "planes", range = 0..3)]
+            plane_indices: Vec<i64>,
+            mode: Option<String>,
+            #[filter_args(default = true)]
+            fast: bool,
+            #[filter_args(default = 8, range = 1..=16)]
+            bits: u32,
+            #[filter_args(default = Kernel::Bilinear)]
+            kernel: Kernel,
+        }
+
+        let mut map = OwnedMap::new(API::get().unwrap());
+        assert_eq!(
+            Args::from_map(&map),
+            Err(ArgumentError::Missing { key: "radius" })
+        );
+
+        assert_eq!(map.set_int("radius", 3), Ok(()));
+        assert_eq!(map.set_int_array("planes", &[0, 2]), Ok(()));
+        assert_eq!(
+            Args::from_map(&map),
+            Ok(Args {
+                radius: 3,
+                strength: 0.5,
+                plane_indices: vec![0, 2],
+                mode: None,
+                fast: true,
+                bits: 8,
+                kernel: Kernel::Bilinear,
+            })
+        );
+
+        assert_eq!(map.set_str("mode", "fast"), Ok(()));
+        assert_eq!(map.set_int("fast", 0), Ok(()));
+        assert_eq!(map.set_float("strength", 0.25), Ok(()));
+        assert_eq!(map.set_int("bits", 10), Ok(()));
+        assert_eq!(map.set_str("kernel", "bicubic"), Ok(()));
+        assert_eq!(
+            Args::from_map(&map),
+            Ok(Args {
+                radius: 3,
+                strength: 0.25,
+                plane_indices: vec![0, 2],
+                mode: Some("fast".to_owned()),
+                fast: false,
+                bits: 10,
+                kernel: Kernel::Bicubic,
+            })
+        );
+
+        assert_eq!(map.set_str("kernel", "lanczos"), Ok(()));
+        assert_eq!(
+            Args::from_map(&map),
+            Err(ArgumentError::InvalidValue {
+                key: "kernel",
+                value: "\"lanczos\"".to_owned(),
+            })
+        );
+
+        assert_eq!(map.set_int("bits", 1 << 40), Ok(()));
+        assert_eq!(
+            Args::from_map(&map),
+            Err(ArgumentError::Overflow {
+                key: "bits",
+                value: 1 << 40,
+                target: "u32",
+            })
+        );
+
+        assert_eq!(map.set_int("bits", 8), Ok(()));
+        assert_eq!(map.set_str("kernel", "bicubic"), Ok(()));
+        assert_eq!(map.set_int_array("planes", &[1, 3]), Ok(()));
+        assert_eq!(
+            Args::from_map(&map),
+            Err(ArgumentError::OutOfRange {
+                key: "planes",
+                value: "3".to_owned(),
+                range: "0 .. 3",
+            })
+        );
+
+        assert_eq!(map.set_int_array("planes", &[]), Ok(()));
+        assert_eq!(map.set_int("radius", 0), Ok(()));
+        assert_eq!(
+            Args::from_map(&map),
+            Err(ArgumentError::OutOfRange {
+                key: "radius",
+                value: "0".to_owned(),
+                range: "1 ..= 100",
+            })
+        );
+
+        assert_eq!(map.set_float("radius", 1.0), Ok(()));
+        assert_eq!(
+            Args::from_map(&map),
+            Err(ArgumentError::Map {
+                key: "radius",
+                source: map::Error::WrongValueType,
+            })
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Kernel {
+        Bilinear,
+        Bicubic,
+    }
+
+    impl plugins::EnumArgument for Kernel {
+        type Repr<'map> = &'map str;
+
+        fn from_repr(x: &str) -> Option<Self> {
+            match x {
+                "bilinear" => Some(Kernel::Bilinear),
+                "bicubic" => Some(Kernel::Bicubic),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn filter_parameters() {
+        use plugins::{ArgumentError, FilterParameter};
+
+        fn get<'map, 'elem: 'map, T: FilterParameter<'map, 'elem>>(
+            map: &'map Map<'elem>,
+            key: &'static str,
+        ) -> Result<T, ArgumentError> {
+            T::get_from_map(map, key)
+        }
+
+        let mut map = OwnedMap::new(API::get().unwrap());
+        assert_eq!(map.set_int("int", 1 << 40), Ok(()));
+        assert_eq!(map.set_int_array("ints", &[0, 1, -1]), Ok(()));
+        assert_eq!(map.set_float("float", 0.5), Ok(()));
+        assert_eq!(map.set_str("str", "bicubic"), Ok(()));
+        assert_eq!(map.append_str("str", "lanczos"), Ok(()));
+
+        assert_eq!(get::<i64>(&map, "int"), Ok(1 << 40));
+        assert_eq!(
+            get::<i32>(&map, "int"),
+            Err(ArgumentError::Overflow {
+                key: "int",
+                value: 1 << 40,
+                target: "i32",
+            })
+        );
+        assert_eq!(get::<Vec<i32>>(&map, "ints"), Ok(vec![0, 1, -1]));
+        assert_eq!(
+            get::<Vec<u32>>(&map, "ints"),
+            Err(ArgumentError::Overflow {
+                key: "ints",
+                value: -1,
+                target: "u32",
+            })
+        );
+        assert_eq!(
+            get::<Vec<bool>>(&map, "ints"),
+            Err(ArgumentError::InvalidValue {
+                key: "ints",
+                value: "-1".to_owned(),
+            })
+        );
+        assert_eq!(map.set_int_array("bools", &[0, 1]), Ok(()));
+        assert_eq!(get::<Vec<bool>>(&map, "bools"), Ok(vec![false, true]));
+        assert_eq!(
+            get::<bool>(&map, "int"),
+            Err(ArgumentError::InvalidValue {
+                key: "int",
+                value: (1i64 << 40).to_string(),
+            })
+        );
+        assert_eq!(get::<Option<usize>>(&map, "missing"), Ok(None));
+        assert_eq!(get::<Option<Vec<i64>>>(&map, "missing"), Ok(None));
+        assert_eq!(get::<f32>(&map, "float"), Ok(0.5));
+        assert_eq!(get::<&str>(&map, "str"), Ok("bicubic"));
+        assert_eq!(get::<String>(&map, "str"), Ok("bicubic".to_owned()));
+        assert_eq!(get::<Kernel>(&map, "str"), Ok(Kernel::Bicubic));
+        assert_eq!(
+            get::<Vec<Kernel>>(&map, "str"),
+            Err(ArgumentError::InvalidValue {
+                key: "str",
+                value: "\"lanczos\"".to_owned(),
+            })
+        );
+        assert_eq!(
+            get::<Option<String>>(&map, "int"),
+            Err(ArgumentError::Map {
+                key: "int",
+                source: map::Error::WrongValueType,
+            })
+        );
+    }
+
+    #[test]
+    fn map_macro() {
+        let planes = vec![0, 1];
+        let map = crate::map! {
+            "width" => 1920,
+            "gamma" => 2.5,
+            "kernel" => "spline36",
+            "planes" => [0, 1],
+            "planes_vec" => &planes,
+            "flags" => [true, false],
+            "blob" => &[1u8, 2][..],
+            "empty" => Vec::<f64>::new(),
+        }
+        .unwrap();
+
+        assert_eq!(map.key_count(), 8);
+        assert_eq!(map.get_int("width"), Ok(1920));
+        assert_eq!(map.get_float("gamma"), Ok(2.5));
+        assert_eq!(map.get_str("kernel"), Ok("spline36"));
+        assert_eq!(map.get_int_array("planes"), Ok(&[0, 1][..]));
+        assert_eq!(map.get_int_array("planes_vec"), Ok(&[0, 1][..]));
+        assert_eq!(map.get_int_array("flags"), Ok(&[1, 0][..]));
+        assert_eq!(map.get_data("blob"), Ok(&[1, 2][..]));
+        assert_eq!(map.value_type("empty"), Ok(ValueType::Float));
+        assert_eq!(map.value_count("empty"), Ok(0));
+
+        let map = crate::map! { "x" => 1, "x" => "replaced" }.unwrap();
+        assert_eq!(map.get_str("x"), Ok("replaced"));
+
+        assert_eq!(crate::map! {}.unwrap().key_count(), 0);
+        assert_eq!(
+            crate::map! { "in valid" => 1 }.err(),
+            Some(map::Error::InvalidKey(
+                map::InvalidKeyError::InvalidCharacter(3)
+            ))
+        );
+    }
+
     #[test]
     fn create_core() {
         let api = API::get().unwrap();
@@ -670,3 +1711,339 @@ mod need_api {
         assert_eq!(core.info().num_threads, 3);
     }
 }
+
+// Tests which don't need VapourSynth at all.
+mod no_api {
+    use super::*;
+
+    #[test]
+    fn python_exception_parsing() {
+        let message = "Python exception: name 'x' is not defined\n\n\
+                       Traceback (most recent call last):\n  \
+                       File \"src/cython/vapoursynth.pyx\", line 3365, in vapoursynth._vpy_evaluate\n  \
+                       File \"script.vpy\", line 7, in <module>\n    \
+                       clip = x\n\
+                       NameError: name 'x' is not defined\n";
+
+        assert_eq!(
+            vsscript::PythonException::parse(message),
+            Some(vsscript::PythonException {
+                kind: "NameError".to_owned(),
+                file: Some("script.vpy".to_owned()),
+                line: Some(7),
+            })
+        );
+
+        // Continuation lines of a multi-line exception message aren't taken as the exception type.
+        let message = "Python exception: bad value\n\n\
+                       Traceback (most recent call last):\n  \
+                       File \"script.vpy\", line 3, in <module>\n    \
+                       raise ValueError('bad value\\ncontinuation')\n\
+                       ValueError: bad value\n\
+                       continuation\n";
+
+        assert_eq!(
+            vsscript::PythonException::parse(message),
+            Some(vsscript::PythonException {
+                kind: "ValueError".to_owned(),
+                file: Some("script.vpy".to_owned()),
+                line: Some(3),
+            })
+        );
+
+        assert_eq!(
+            vsscript::PythonException::parse("Failed to initialize VapourSynth"),
+            None
+        );
+    }
+
+    #[test]
+    fn frame_prop_conversions() {
+        use frame_props::*;
+
+        assert_eq!(
+            MatrixCoefficients::try_from(9),
+            Ok(MatrixCoefficients::BT2020NCL)
+        );
+        assert_eq!(MatrixCoefficients::try_from(11), Err(11));
+        assert_eq!(i64::from(TransferCharacteristics::AribB67), 18);
+        assert_eq!(ColorPrimaries::try_from(22), Ok(ColorPrimaries::EBU3213E));
+        assert_eq!(ChromaLocation::try_from(-1), Err(-1));
+        assert_eq!(PictType::I.as_byte(), b'I');
+        assert_eq!(PictType::from_byte(b'B'), PictType::B);
+        assert_eq!(PictType::from_byte(b'S'), PictType::Other(b'S'));
+        assert_eq!(PictType::Other(b'S').as_byte(), b'S');
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn filter_args_string() {
+        use plugins::FilterArgs;
+
+        #[derive(FilterArgs)]
+        #[allow(dead_code)]
+        struct Args<'core> {
+            clip: node::Node<'core>,
+            #[filter_args(default = 1)]
+            radius: i64,
+            #[filter_args(rename = "planes")]
+            plane_indices: Vec<i64>,
+            clips: Option<Vec<node::Node<'core>>>,
+            func: Option<function::Function<'core>>,
+            name: String,
+        }
+
+        assert_eq!(
+            Args::args(),
+            "clip:vnode;radius:int:opt;planes:int[]:empty;clips:vnode[]:opt:empty;\
+             func:func:opt;name:data;"
+        );
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn filter_function_attribute() {
+        use crate::core::CoreRef;
+        use plugins::{Filter, FilterFunction};
+        use prelude::*;
+
+        #[crate::filter_function(name = "Passthrough")]
+        fn create_passthrough<'core>(
+            _core: CoreRef<'core>,
+            clip: Node<'core>,
+            radius: Option<i64>,
+            r#type: &[u8],
+            _api: API,
+        ) -> anyhow::Result<Option<Box<dyn Filter<'core> + 'core>>> {
+            let _ = (clip, radius, r#type);
+            Ok(None)
+        }
+
+        #[crate::filter_function(name = "Blank", type_name = BlankFilter)]
+        fn create_blank<'core>(
+            _api: API,
+        ) -> anyhow::Result<Option<Box<dyn Filter<'core> + 'core>>> {
+            Ok(None)
+        }
+
+        let function = PassthroughFunction::new();
+        assert_eq!(function.name(), "Passthrough");
+        assert_eq!(function.args(), "clip:vnode;radius:int:opt;type:data;");
+        assert_eq!(function.return_type(), "clip:vnode;");
+
+        let function = BlankFilter::new();
+        assert_eq!(function.name(), "Blank");
+        assert_eq!(function.args(), "");
+
+        assert_eq!(test_plugin::SampleFunction::new().name(), "Sample");
+    }
+
+    #[test]
+    fn plugin_validation() {
+        use plugins::*;
+
+        const METADATA: Metadata = Metadata {
+            identifier: "com.example.valid",
+            namespace: "valid_2",
+            name: "Valid Plugin",
+            read_only: false,
+        };
+        const _: () = assert_valid(METADATA.validate());
+
+        let invalid = |identifier, namespace, name| {
+            Metadata {
+                identifier,
+                namespace,
+                name,
+                ..METADATA
+            }
+            .validate()
+        };
+        assert_eq!(
+            invalid("com.example two", "valid", "Valid"),
+            Err(ValidationError::InvalidIdentifier)
+        );
+        assert_eq!(
+            invalid("", "valid", "Valid"),
+            Err(ValidationError::InvalidIdentifier)
+        );
+        assert_eq!(
+            invalid("com.example", "Invalid", "Valid"),
+            Err(ValidationError::InvalidNamespace)
+        );
+        assert_eq!(
+            invalid("com.example", "_invalid", "Valid"),
+            Err(ValidationError::InvalidNamespace)
+        );
+        assert_eq!(
+            invalid("com.example", "valid", ""),
+            Err(ValidationError::InvalidPluginName)
+        );
+
+        assert_eq!(validate_function_name("Invert_2"), Ok(()));
+        assert_eq!(
+            validate_function_name("2Invert"),
+            Err(ValidationError::InvalidFunctionName)
+        );
+        assert_eq!(
+            validate_function_name("r#type"),
+            Err(ValidationError::InvalidFunctionName)
+        );
+
+        assert_eq!(validate_signature(""), Ok(()));
+        assert_eq!(
+            validate_signature("clip:vnode;planes:int[]:opt:empty;func:func:opt"),
+            Ok(())
+        );
+        assert_eq!(
+            validate_signature("clip: vnode;"),
+            Err(ValidationError::InvalidArgumentType)
+        );
+        assert_eq!(
+            validate_signature("clip:clip;"),
+            Err(ValidationError::InvalidArgumentType)
+        );
+        assert_eq!(
+            validate_signature("clip;"),
+            Err(ValidationError::MissingArgumentType)
+        );
+        assert_eq!(
+            validate_signature("clip:vnode;;"),
+            Err(ValidationError::InvalidArgumentName)
+        );
+        assert_eq!(
+            validate_signature("_clip:vnode;"),
+            Err(ValidationError::InvalidArgumentName)
+        );
+        assert_eq!(
+            validate_signature("clip:vnode:optional;"),
+            Err(ValidationError::InvalidArgumentModifier)
+        );
+        assert_eq!(
+            ValidationError::MissingArgumentType.to_string(),
+            "An argument is missing its type"
+        );
+    }
+
+    #[test]
+    fn plugin_version() {
+        use plugins::Version;
+
+        assert_eq!(Version::parse("0.5.5"), Version::new(0, 5));
+        assert_eq!(Version::parse("12.34.0-rc.1+build"), Version::new(12, 34));
+        assert_eq!(Version::parse("2-alpha"), Version::new(2, 0));
+        assert_eq!(Version::parse("99999.1"), Version::new(u16::MAX, 1));
+        assert_eq!(Version::parse(""), Version::new(0, 0));
+
+        assert!(Version::new(4, 1) > Version::API);
+        assert_eq!(Version::new(3, 7).to_string(), "3.7");
+
+        let version = Version::new(3, 7);
+        assert_eq!(version.ffi_type(), 3 << 16 | 7);
+        assert_eq!(Version::from_ffi_type(version.ffi_type()), version);
+    }
+
+    #[test]
+    fn filter_parameter_types() {
+        use crate::core::CoreRef;
+        use plugins::{EnumArgument, Filter, FilterFunction};
+        use prelude::*;
+
+        enum Mode {
+            Fast,
+            Slow,
+        }
+
+        impl EnumArgument for Mode {
+            type Repr<'map> = i64;
+
+            fn from_repr(x: i64) -> Option<Self> {
+                match x {
+                    0 => Some(Mode::Fast),
+                    1 => Some(Mode::Slow),
+                    _ => None,
+                }
+            }
+        }
+
+        make_filter_function! {
+            TypesFunction, "Types"
+
+            #[allow(clippy::too_many_arguments)]
+            fn create_types<'core>(
+                _api: API,
+                _core: CoreRef<'core>,
+                clip: Node<'core>,
+                radius: u32,
+                planes: Vec<usize>,
+                strength: Option<f32>,
+                fast: bool,
+                name: Option<&str>,
+                label: String,
+                mode: Option<Mode>,
+            ) -> anyhow::Result<Option<Box<dyn Filter<'core> + 'core>>> {
+                let _ = (clip, radius, planes, strength, fast, name, label);
+                let _ = matches!(mode, Some(Mode::Fast | Mode::Slow));
+                Ok(None)
+            }
+        }
+
+        assert_eq!(
+            plugins::validate_signature(TypesFunction::new().args()),
+            Ok(())
+        );
+        assert_eq!(
+            TypesFunction::new().args(),
+            "clip:vnode;radius:int;planes:int[]:empty;strength:float:opt;fast:int;\
+             name:data:opt;label:data;mode:int:opt;"
+        );
+    }
+
+    #[cfg(feature = "macros")]
+    struct NoopFunction;
+
+    #[cfg(feature = "macros")]
+    impl plugins::FilterFunction for NoopFunction {
+        fn name(&self) -> &str {
+            "Noop"
+        }
+
+        fn args(&self) -> &str {
+            ""
+        }
+
+        fn create<'core>(
+            &self,
+            _api: api::API,
+            _core: crate::core::CoreRef<'core>,
+            _args: &map::Map<'core>,
+        ) -> anyhow::Result<Option<Box<dyn plugins::Filter<'core> + 'core>>> {
+            Ok(None)
+        }
+    }
+
+    #[cfg(feature = "macros")]
+    #[crate::plugin(
+        identifier = "com.example.vapoursynth-rs.tests",
+        namespace = "tests",
+        name = "Test Plugin",
+        functions = [super::NoopFunction],
+        version = crate::plugins::Version::new(1, 2),
+    )]
+    mod test_plugin {
+        use crate::core::CoreRef;
+        use crate::map;
+        use crate::plugins::Filter;
+        use crate::prelude::*;
+
+        #[crate::filter_function(name = "Sample")]
+        pub fn create_sample<'core>(
+            _api: API,
+            _core: CoreRef<'core>,
+            clips: map::ValueIter<'_, 'core, Node<'core>>,
+        ) -> anyhow::Result<Option<Box<dyn Filter<'core> + 'core>>> {
+            let _ = clips;
+            Ok(None)
+        }
+    }
+}
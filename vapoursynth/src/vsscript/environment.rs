@@ -1,13 +1,16 @@
 use std::ffi::{CStr, CString};
+use std::fmt;
 use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_char, c_int, c_void};
 use std::path::Path;
 use std::ptr;
 use std::ptr::NonNull;
+use std::{panic, process};
 use vapoursynth_sys as ffi;
 
-use crate::api::API;
+use crate::api::{API, MessageType};
 use crate::core::CoreRef;
-use crate::map::Map;
+use crate::map::{Map, OwnedMap, Value};
 use crate::node::Node;
 use crate::vsscript::errors::Result;
 use crate::vsscript::*;
@@ -31,10 +34,246 @@ enum EvaluateScriptArgs<'a> {
     File(&'a Path, EvalFlags),
 }
 
+/// A node set for output in the script environment.
+#[derive(Debug, Clone)]
+pub struct Output<'a> {
+    /// The output index.
+    pub index: i32,
+
+    /// The output node.
+    pub node: Node<'a>,
+
+    /// The alpha node, if any.
+    pub alpha_node: Option<Node<'a>>,
+
+    /// The alternative output mode, as passed to `set_output(alt_output = ...)` in the script.
+    ///
+    /// `0` is the normal output. Other values request an alternative packing of the output data,
+    /// which should be handled by the output code.
+    pub alt_output_mode: i32,
+}
+
+/// The script filename reported in tracebacks of scripts evaluated from a string.
+const DEFAULT_SCRIPT_FILENAME: &str = "<string>";
+
+/// A function configuring a core before a script environment is created on it.
+type ConfigureCoreFn<'a> = Box<dyn for<'core> FnOnce(CoreRef<'core>) + 'a>;
+
+/// A function handling the log messages of a core.
+type LogHandlerFn = Box<dyn Fn(MessageType, &str) + Send + Sync>;
+
+/// Calls the log handler passed in `user_data`.
+unsafe extern "C" fn call_log_handler(
+    message_type: c_int,
+    message: *const c_char,
+    user_data: *mut c_void,
+) {
+    let closure = panic::AssertUnwindSafe(|| {
+        let handler = &*(user_data as *const LogHandlerFn);
+        let message_type =
+            MessageType::from_ffi_type(message_type).unwrap_or(MessageType::Information);
+        let message = CStr::from_ptr(message).to_string_lossy();
+
+        handler(message_type, &message);
+    });
+
+    if panic::catch_unwind(closure).is_err() {
+        process::abort();
+    }
+}
+
+/// Frees the log handler passed in `user_data`.
+unsafe extern "C" fn free_log_handler(user_data: *mut c_void) {
+    drop(Box::from_raw(user_data as *mut LogHandlerFn));
+}
+
+/// A builder for a script environment.
+///
+/// Unlike `Environment::new()`, which lets the script create a core with the default options, the
+/// builder creates the core up front so it can be configured before any script runs.
+pub struct EnvironmentBuilder<'a> {
+    core_flags: c_int,
+    thread_count: Option<i32>,
+    max_cache_size: Option<i64>,
+    script_filename: Option<String>,
+    log_handlers: Vec<LogHandlerFn>,
+    configure_core: Option<ConfigureCoreFn<'a>>,
+}
+
+impl fmt::Debug for EnvironmentBuilder<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EnvironmentBuilder")
+            .field("core_flags", &self.core_flags)
+            .field("thread_count", &self.thread_count)
+            .field("max_cache_size", &self.max_cache_size)
+            .field("script_filename", &self.script_filename)
+            .field("log_handlers", &self.log_handlers.len())
+            .field("configure_core", &self.configure_core.is_some())
+            .finish()
+    }
+}
+
+impl Default for EnvironmentBuilder<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> EnvironmentBuilder<'a> {
+    /// Creates a builder with the default options.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            core_flags: 0,
+            thread_count: None,
+            max_cache_size: None,
+            script_filename: None,
+            log_handlers: Vec::new(),
+            configure_core: None,
+        }
+    }
+
+    #[inline]
+    fn core_flag(mut self, flag: ffi::VSCoreCreationFlags, enabled: bool) -> Self {
+        if enabled {
+            self.core_flags |= flag as c_int;
+        } else {
+            self.core_flags &= !(flag as c_int);
+        }
+        self
+    }
+
+    /// Enables the graph inspection API on the core.
+    #[inline]
+    pub fn enable_graph_inspection(self, enabled: bool) -> Self {
+        self.core_flag(ffi::VSCoreCreationFlags_ccfEnableGraphInspection, enabled)
+    }
+
+    /// Disables autoloading of the plugins from the default locations.
+    #[inline]
+    pub fn disable_auto_loading(self, disabled: bool) -> Self {
+        self.core_flag(ffi::VSCoreCreationFlags_ccfDisableAutoLoading, disabled)
+    }
+
+    /// Keeps the plugin libraries loaded when the core is freed.
+    #[inline]
+    pub fn disable_library_unloading(self, disabled: bool) -> Self {
+        self.core_flag(
+            ffi::VSCoreCreationFlags_ccfDisableLibraryUnloading,
+            disabled,
+        )
+    }
+
+    /// Sets the number of worker threads of the core.
+    ///
+    /// If the requested number of threads is zero or lower, the number of hardware threads will be
+    /// detected and used.
+    #[inline]
+    pub fn thread_count(mut self, threads: i32) -> Self {
+        self.thread_count = Some(threads);
+        self
+    }
+
+    /// Sets the maximum size of the framebuffer cache of the core, in bytes.
+    #[inline]
+    pub fn max_cache_size(mut self, bytes: i64) -> Self {
+        self.max_cache_size = Some(bytes);
+        self
+    }
+
+    /// Sets the filename reported in errors and tracebacks of scripts evaluated from a string.
+    ///
+    /// Defaults to `<string>`.
+    #[inline]
+    pub fn script_filename<S: Into<String>>(mut self, filename: S) -> Self {
+        self.script_filename = Some(filename.into());
+        self
+    }
+
+    /// Adds a handler for the log messages of the core.
+    ///
+    /// The handler is installed before any script runs, so it receives all messages emitted
+    /// during script evaluation. It's removed when the environment is dropped. The handler may
+    /// be called from any thread; if it panics, the process is aborted.
+    #[inline]
+    pub fn log_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(MessageType, &str) + Send + Sync + 'static,
+    {
+        self.log_handlers.push(Box::new(handler));
+        self
+    }
+
+    /// Sets a function which is called with the newly created core before the script environment
+    /// is created.
+    ///
+    /// This can be used for any configuration not covered by the builder, for example loading
+    /// plugins.
+    #[inline]
+    pub fn configure_core<F>(mut self, f: F) -> Self
+    where
+        F: for<'core> FnOnce(CoreRef<'core>) + 'a,
+    {
+        self.configure_core = Some(Box::new(f));
+        self
+    }
+
+    /// Creates the core and a script environment on it.
+    ///
+    /// The ownership of the core is transferred to the environment, so the core is freed when the
+    /// environment is dropped.
+    pub fn build(self) -> Result<Environment> {
+        let api = API::get().ok_or(Error::NoAPI)?;
+        let vsscript_api = VSScriptAPI::get().expect("VSScript API not available");
+
+        let script_filename = CString::new(
+            self.script_filename
+                .unwrap_or_else(|| DEFAULT_SCRIPT_FILENAME.to_owned()),
+        )?;
+
+        let core = unsafe { CoreRef::from_ptr(api.create_core_with_flags(self.core_flags)) };
+        for handler in self.log_handlers {
+            unsafe {
+                api.add_log_handler(
+                    Some(call_log_handler),
+                    Some(free_log_handler),
+                    Box::into_raw(Box::new(handler)) as *mut c_void,
+                    core.ptr(),
+                );
+            }
+        }
+        if let Some(threads) = self.thread_count {
+            core.set_thread_count(threads);
+        }
+        if let Some(bytes) = self.max_cache_size {
+            core.set_max_cache_size(bytes);
+        }
+        if let Some(configure_core) = self.configure_core {
+            configure_core(core);
+        }
+
+        let handle = unsafe { (vsscript_api.handle().createScript.unwrap())(core.ptr()) };
+
+        if handle.is_null() {
+            // The ownership of the core wasn't transferred, so free it along with the log
+            // handlers.
+            unsafe { api.free_core(core.ptr()) };
+            Err(Error::ScriptCreationFailed)
+        } else {
+            Ok(Environment {
+                handle: unsafe { NonNull::new_unchecked(handle) },
+                script_filename,
+            })
+        }
+    }
+}
+
 /// A wrapper for the VSScript environment.
 #[derive(Debug)]
 pub struct Environment {
     handle: NonNull<ffi::VSScript>,
+    script_filename: CString,
 }
 
 unsafe impl Send for Environment {}
@@ -44,6 +283,8 @@ impl Drop for Environment {
     #[inline]
     fn drop(&mut self) {
         let api = VSScriptAPI::get().expect("VSScript API not available");
+        // Freeing the script frees its core, which removes the log handlers added by
+        // `EnvironmentBuilder`.
         unsafe {
             (api.handle().freeScript.unwrap())(self.handle.as_ptr());
         }
@@ -51,15 +292,16 @@ impl Drop for Environment {
 }
 
 impl Environment {
-    /// Retrieves the VSScript error message.
+    /// Retrieves the VSScript error message and the script exit code.
     ///
     /// # Safety
     /// This function must only be called if an error is present.
     #[inline]
-    unsafe fn error(&self) -> CString {
+    unsafe fn error(&self) -> VSScriptError {
         let api = VSScriptAPI::get().expect("VSScript API not available");
         let message = (api.handle().getError.unwrap())(self.handle.as_ptr());
-        CStr::from_ptr(message).to_owned()
+        let exit_code = (api.handle().getExitCode.unwrap())(self.handle.as_ptr());
+        VSScriptError::new(CStr::from_ptr(message).to_owned(), exit_code)
     }
 
     /// Creates an empty script environment.
@@ -75,10 +317,17 @@ impl Environment {
         } else {
             Ok(Self {
                 handle: unsafe { NonNull::new_unchecked(handle) },
+                script_filename: CString::new(DEFAULT_SCRIPT_FILENAME).unwrap(),
             })
         }
     }
 
+    /// Returns a builder for a script environment with a pre-configured core.
+    #[inline]
+    pub fn builder<'a>() -> EnvironmentBuilder<'a> {
+        EnvironmentBuilder::new()
+    }
+
     /// Evaluates a script using the VSScript API.
     ///
     /// `self` is taken by a mutable reference mainly to ensure the atomicity of a call to
@@ -91,12 +340,11 @@ impl Environment {
         let rv = match args {
             EvaluateScriptArgs::Script(script) => {
                 let script = CString::new(script)?;
-                let filename = CString::new("<string>").unwrap();
                 unsafe {
                     (api.handle().evaluateBuffer.unwrap())(
                         self.handle.as_ptr(),
                         script.as_ptr(),
-                        filename.as_ptr(),
+                        self.script_filename.as_ptr(),
                     )
                 }
             }
@@ -128,7 +376,7 @@ impl Environment {
         };
 
         if rv != 0 {
-            Err(VSScriptError::new(unsafe { self.error() }).into())
+            Err(unsafe { self.error() }.into())
         } else {
             Ok(())
         }
@@ -162,14 +410,14 @@ impl Environment {
         self.evaluate_script(EvaluateScriptArgs::File(path.as_ref(), flags))
     }
 
-    /// Clears the script environment.
+    /// Clears the script environment: deletes all variables and cancels all outputs.
     ///
-    /// Note: In VapourSynth v4, this is a no-op. To clear the environment,
-    /// drop the Environment and create a new one.
+    /// The core is kept, so nodes retrieved from the environment earlier remain valid.
     #[inline]
-    pub fn clear(&self) {
-        // The clearEnvironment function was removed in VapourSynth v4.
-        // Users should drop and recreate the Environment instead.
+    pub fn clear(&mut self) -> Result<()> {
+        // The clearEnvironment function was removed in VapourSynth v4, so do the same thing from
+        // Python.
+        self.eval_script("__import__('vapoursynth').clear_outputs()\nglobals().clear()\n")
     }
 
     /// Retrieves a node from the script environment. A node in the script must have been marked
@@ -202,14 +450,62 @@ impl Environment {
         Ok((node, alpha_node))
     }
 
+    /// Retrieves all nodes set for output in the script environment, in the order of their
+    /// indices.
+    ///
+    /// Requires VSScript API 4.2 or newer, otherwise `Error::Unsupported` is returned.
+    pub fn outputs(&self) -> Result<Vec<Output<'_>>> {
+        let vsscript_api = VSScriptAPI::get().expect("VSScript API not available");
+        if vsscript_api.minor_version() < 2 {
+            return Err(Error::Unsupported);
+        }
+
+        let get_available_output_nodes = vsscript_api.handle().getAvailableOutputNodes.unwrap();
+
+        // The first call returns the total number of outputs. A negative count is treated as no
+        // outputs.
+        let count = unsafe { get_available_output_nodes(self.handle.as_ptr(), 0, ptr::null_mut()) };
+        let mut indices = vec![0; usize::try_from(count).unwrap_or(0)];
+        if indices.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let count = unsafe {
+            get_available_output_nodes(self.handle.as_ptr(), count, indices.as_mut_ptr())
+        };
+        indices.truncate(usize::try_from(count).unwrap_or(0));
+        indices.sort_unstable();
+
+        indices
+            .into_iter()
+            .map(|index| {
+                let (node, alpha_node) = self.get_output(index)?;
+                let alt_output_mode = unsafe {
+                    (vsscript_api.handle().getAltOutputMode.unwrap())(self.handle.as_ptr(), index)
+                };
+
+                Ok(Output {
+                    index,
+                    node,
+                    alpha_node,
+                    alt_output_mode,
+                })
+            })
+            .collect()
+    }
+
     /// Cancels a node set for output. The node will no longer be available to `get_output()`.
     ///
-    /// Note: In VapourSynth v4, this function has been removed. This is now a no-op that always
-    /// returns Ok. To clear outputs, drop the Environment and create a new one.
-    #[inline]
-    pub fn clear_output(&self, _index: i32) -> Result<()> {
-        // The clearOutput function was removed in VapourSynth v4.
-        Ok(())
+    /// Returns `Error::NoOutput` if there's no output on the requested index.
+    pub fn clear_output(&mut self, index: i32) -> Result<()> {
+        // Check the output exists, the Python function silently ignores missing outputs.
+        self.get_output(index)?;
+
+        // The clearOutput function was removed in VapourSynth v4, so do the same thing from
+        // Python.
+        self.eval_script(&format!(
+            "__import__('vapoursynth').clear_output({index})\n"
+        ))
     }
 
     /// Retrieves the VapourSynth core that was created in the script environment. If a VapourSynth
@@ -258,13 +554,73 @@ impl Environment {
         }
     }
 
+    /// Retrieves a variable from the script environment and converts it to the given type.
+    ///
+    /// This is a shortcut for `get_variable()` with a temporary map, so only the types which don't
+    /// borrow the map can be retrieved, for example `String` but not `&str`.
+    pub fn get<'a, T>(&'a self, name: &str) -> Result<T>
+    where
+        T: for<'map> Value<'map, 'a>,
+    {
+        // OwnedMap needs the API.
+        let api = API::get().ok_or(Error::NoAPI)?;
+
+        let mut map = OwnedMap::new(api);
+        self.get_variable(name, &mut map)?;
+        Ok(T::get_from_map(&map, name)?)
+    }
+
+    /// Sets a variable in the script environment.
+    ///
+    /// This is a shortcut for `set_variables()` with a temporary map.
+    pub fn set<'elem, T>(&self, name: &str, value: &T) -> Result<()>
+    where
+        T: for<'map> Value<'map, 'elem>,
+    {
+        // OwnedMap needs the API.
+        let api = API::get().ok_or(Error::NoAPI)?;
+
+        let mut map = OwnedMap::new(api);
+        T::store_in_map(&mut map, name, value)?;
+        self.set_variables(&map)
+    }
+
     /// Deletes a variable from the script environment.
     ///
-    /// Note: In VapourSynth v4, the clearVariable function has been removed.
-    /// This is now a no-op that always returns Ok.
-    #[inline]
-    pub fn clear_variable(&self, _name: &str) -> Result<()> {
-        // The clearVariable function was removed in VapourSynth v4.
-        Ok(())
+    /// Returns `Error::NoSuchVariable` if there's no variable with the given name.
+    pub fn clear_variable(&mut self, name: &str) -> Result<()> {
+        // Check for NUL bytes, those can't be passed to Python.
+        CString::new(name)?;
+
+        // The clearVariable function was removed in VapourSynth v4, so do the same thing from
+        // Python.
+        let script = format!("del globals()[{}]\n", python_str_literal(name));
+        match self.eval_script(&script) {
+            Err(Error::VSScript(ref e))
+                if e.exception()
+                    .is_some_and(|exception| exception.kind == "KeyError") =>
+            {
+                Err(Error::NoSuchVariable)
+            }
+            rv => rv,
+        }
+    }
+}
+
+/// Returns a Python string literal with the given contents.
+///
+/// Everything except ASCII alphanumerics and underscores is escaped, so the literal is valid
+/// regardless of the contents.
+fn python_str_literal(s: &str) -> String {
+    let mut literal = String::with_capacity(s.len() + 2);
+    literal.push('\'');
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            literal.push(c);
+        } else {
+            literal.push_str(&format!("\\U{:08x}", c as u32));
+        }
     }
+    literal.push('\'');
+    literal
 }
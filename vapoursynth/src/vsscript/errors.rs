@@ -1,8 +1,10 @@
-use std::ffi::{CString, NulError};
+use std::ffi::{CStr, CString, NulError};
 use std::{fmt, io};
 
 use thiserror::Error;
 
+use crate::map;
+
 /// The error type for `vsscript` operations.
 #[derive(Error, Debug)]
 pub enum Error {
@@ -26,6 +28,10 @@ pub enum Error {
     NoAPI,
     #[error("Failed to create VSScript environment")]
     ScriptCreationFailed,
+    #[error("The loaded VSScript library doesn't support this operation")]
+    Unsupported,
+    #[error("Couldn't convert the variable")]
+    Map(#[source] map::Error),
 }
 
 impl From<NulError> for Error {
@@ -35,6 +41,13 @@ impl From<NulError> for Error {
     }
 }
 
+impl From<map::Error> for Error {
+    #[inline]
+    fn from(x: map::Error) -> Self {
+        Error::Map(x)
+    }
+}
+
 impl From<VSScriptError> for Error {
     #[inline]
     fn from(x: VSScriptError) -> Self {
@@ -46,19 +59,117 @@ pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 /// A container for a VSScript error.
 #[derive(Error, Debug)]
-pub struct VSScriptError(CString);
+pub struct VSScriptError {
+    message: CString,
+    exit_code: i32,
+    exception: Option<PythonException>,
+}
+
+/// Information about the Python exception that caused a VSScript error.
+///
+/// This is parsed from the traceback contained in the error message.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct PythonException {
+    /// The exception type, for example `NameError` or `vapoursynth.Error`.
+    pub kind: String,
+
+    /// The file of the innermost traceback entry, if any.
+    pub file: Option<String>,
+
+    /// The line of the innermost traceback entry, if any.
+    pub line: Option<usize>,
+}
 
 impl fmt::Display for VSScriptError {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0.to_string_lossy())
+        write!(f, "{}", self.message.to_string_lossy())
     }
 }
 
 impl VSScriptError {
-    /// Creates a new `VSScriptError` with the given error message.
+    /// Creates a new `VSScriptError` with the given error message and script exit code.
+    #[inline]
+    pub(crate) fn new(message: CString, exit_code: i32) -> Self {
+        let exception = PythonException::parse(&message.to_string_lossy());
+        Self {
+            message,
+            exit_code,
+            exception,
+        }
+    }
+
+    /// Returns the error message.
+    #[inline]
+    pub fn message(&self) -> &CStr {
+        &self.message
+    }
+
+    /// Returns the exit code reported by the script, for example the value passed to
+    /// `sys.exit()`.
+    #[inline]
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+
+    /// Returns the Python exception that caused the error, if it could be determined.
     #[inline]
-    pub(crate) fn new(message: CString) -> Self {
-        VSScriptError(message)
+    pub fn exception(&self) -> Option<&PythonException> {
+        self.exception.as_ref()
+    }
+}
+
+impl PythonException {
+    /// Parses the exception information out of a VSScript error message containing a Python
+    /// traceback.
+    ///
+    /// Only the last traceback is considered, in case of chained exceptions. The exception type is
+    /// taken from the first unindented line following its entries, so that continuation lines of
+    /// multi-line exception messages aren't mistaken for it.
+    pub(crate) fn parse(message: &str) -> Option<Self> {
+        const TRACEBACK_HEADER: &str = "Traceback (most recent call last):";
+
+        let traceback = &message[message.rfind(TRACEBACK_HEADER)? + TRACEBACK_HEADER.len()..];
+
+        let mut file = None;
+        let mut line = None;
+
+        for text in traceback.lines() {
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            // Traceback entries look like `  File "<string>", line 3, in <module>`, everything
+            // else that is indented is source code.
+            if text.starts_with(char::is_whitespace) {
+                if let Some(entry) = text.trim_start().strip_prefix("File \"")
+                    && let Some((name, rest)) = entry.split_once('"')
+                {
+                    file = Some(name.to_owned());
+                    line = rest
+                        .strip_prefix(", line ")
+                        .and_then(|rest| rest.split(',').next())
+                        .and_then(|number| number.trim().parse().ok());
+                }
+
+                continue;
+            }
+
+            // The first unindented line ends the traceback and is `ExceptionType: message` or
+            // just `ExceptionType`.
+            let name = text.split(':').next().unwrap().trim();
+            let is_name = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+
+            return is_name.then(|| Self {
+                kind: name.to_owned(),
+                file,
+                line,
+            });
+        }
+
+        None
     }
 }
@@ -17,6 +17,13 @@ unsafe impl Sync for VSScriptAPI {}
 
 static VSSCRIPT_API_LOADER: OnceLock<Option<VSScriptAPILoader>> = OnceLock::new();
 
+/// The minimum VSScript API minor version.
+///
+/// The bindings are generated for a newer minor version, so functions that were added later (for
+/// example `getAvailableOutputNodes()` from 4.2) must only be called after checking
+/// `VSScriptAPI::minor_version()`.
+const VSSCRIPT_API_MINOR_REQUIRED: u32 = 1;
+
 impl VSScriptAPI {
     /// Retrieves the VSScript API.
     ///
@@ -35,7 +42,7 @@ impl VSScriptAPI {
                         .find_map(|path| unsafe { VSScriptAPILoader::new(*path) }.ok())
                 }?;
 
-                let version = ffi::VSSCRIPT_API_MAJOR << 16 | ffi::VSSCRIPT_API_MINOR;
+                let version = ffi::VSSCRIPT_API_MAJOR << 16 | VSSCRIPT_API_MINOR_REQUIRED;
                 let handle =
                     unsafe { loader.getVSScriptAPI(version as i32) } as *mut ffi::VSSCRIPTAPI;
 
@@ -51,11 +58,10 @@ impl VSScriptAPI {
                             ffi::VSSCRIPT_API_MAJOR,
                             major
                         );
-                    } else if (minor as u32) < ffi::VSSCRIPT_API_MINOR {
+                    } else if (minor as u32) < VSSCRIPT_API_MINOR_REQUIRED {
                         panic!(
                             "Invalid VSScript minor API version (expected: >= {}, got: {})",
-                            ffi::VSSCRIPT_API_MINOR,
-                            minor
+                            VSSCRIPT_API_MINOR_REQUIRED, minor
                         );
                     }
 
@@ -83,10 +89,20 @@ impl VSScriptAPI {
     pub(crate) fn handle(&self) -> &ffi::VSSCRIPTAPI {
         unsafe { self.handle.as_ref() }
     }
+
+    /// Returns the minor API version supported by the loaded VSScript library.
+    #[inline]
+    pub(crate) fn minor_version(&self) -> u32 {
+        let api_version = unsafe { (self.handle().getAPIVersion.unwrap())() };
+        (api_version & 0xFFFF) as u32
+    }
 }
 
 mod errors;
-pub use self::errors::{Error, VSScriptError};
+pub use self::errors::{Error, PythonException, VSScriptError};
 
 mod environment;
-pub use self::environment::{Environment, EvalFlags};
+pub use self::environment::{Environment, EnvironmentBuilder, EvalFlags, Output};
+
+mod shared;
+pub use self::shared::{SharedCore, SharedEnvironment, SharedFrame, SharedNode};
@@ -0,0 +1,257 @@
+use std::ops::Deref;
+use std::sync::Arc;
+use std::thread;
+
+use crate::core::{CoreRef, Pending};
+use crate::frame::FrameRef;
+use crate::node::{GetFrameError, Node};
+use crate::vsscript::errors::Result;
+
+use super::Environment;
+
+/// A reference-counted script environment.
+///
+/// Unlike the nodes, frames and cores retrieved from an `Environment`, the handles retrieved from
+/// a `SharedEnvironment` aren't tied to a borrow of the environment. Instead, each of them keeps
+/// the environment alive, so they can be stored in structs and sent to other threads freely.
+///
+/// The environment is accessible through `Deref`, but it can't be modified while shared. Use
+/// `try_unwrap()` to get it back.
+///
+/// Freeing the environment waits for the worker threads of its core, so it's never freed from a
+/// `SharedNode::get_frame_async()` callback, which runs on one of them: if the callback drops the
+/// last handle, the environment is freed on a new thread instead.
+///
+/// # Example
+/// ```no_run
+/// # use vapoursynth::prelude::*;
+/// # use vapoursynth::vsscript::SharedEnvironment;
+/// # use std::thread;
+/// # fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let env = SharedEnvironment::new(Environment::from_file("test.vpy", EvalFlags::Nothing)?);
+/// let (node, _alpha_node) = env.get_output(0)?;
+/// drop(env);
+///
+/// // The node keeps the environment alive.
+/// thread::spawn(move || {
+///     let frame = node.get_frame(0).unwrap();
+///     println!("{}", frame.frame().width(0));
+/// })
+/// .join()
+/// .unwrap();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedEnvironment {
+    environment: Arc<Environment>,
+    // Requests made with `SharedNode::get_frame_async()` whose callbacks haven't returned yet.
+    requests: Arc<Pending>,
+}
+
+/// A node retrieved from a `SharedEnvironment`.
+#[derive(Debug, Clone)]
+pub struct SharedNode {
+    // The node must be dropped before the environment, which is ensured by the field order.
+    node: Node<'static>,
+    environment: SharedEnvironment,
+}
+
+/// A frame retrieved from a `SharedNode`.
+#[derive(Debug, Clone)]
+pub struct SharedFrame {
+    // The frame must be dropped before the environment, which is ensured by the field order.
+    frame: FrameRef<'static>,
+    environment: SharedEnvironment,
+}
+
+/// The core of a `SharedEnvironment`.
+#[derive(Debug, Clone)]
+pub struct SharedCore {
+    core: CoreRef<'static>,
+    environment: SharedEnvironment,
+}
+
+impl From<Environment> for SharedEnvironment {
+    #[inline]
+    fn from(environment: Environment) -> Self {
+        Self::new(environment)
+    }
+}
+
+impl Deref for SharedEnvironment {
+    type Target = Environment;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.environment
+    }
+}
+
+impl SharedEnvironment {
+    /// Wraps `environment` in a `SharedEnvironment`.
+    #[inline]
+    pub fn new(environment: Environment) -> Self {
+        Self {
+            environment: Arc::new(environment),
+            requests: Arc::new(Pending::new()),
+        }
+    }
+
+    /// Returns the environment if there are no other handles referring to it.
+    ///
+    /// Otherwise, returns `self` back.
+    ///
+    /// The callbacks of `SharedNode::get_frame_async()` hold handles, so this first waits for all
+    /// of them to return. Calling it from such a callback deadlocks.
+    pub fn try_unwrap(self) -> std::result::Result<Environment, Self> {
+        self.requests.wait();
+
+        let requests = self.requests;
+        Arc::try_unwrap(self.environment).map_err(|environment| Self {
+            environment,
+            requests,
+        })
+    }
+
+    /// Retrieves a node from the script environment, like `Environment::get_output()`.
+    ///
+    /// The returned nodes keep the environment alive.
+    pub fn get_output(&self, index: i32) -> Result<(SharedNode, Option<SharedNode>)> {
+        let (node, alpha_node) = self.environment.get_output(index)?;
+
+        Ok((
+            self.shared_node(node),
+            alpha_node.map(|alpha_node| self.shared_node(alpha_node)),
+        ))
+    }
+
+    /// Retrieves the VapourSynth core, like `Environment::get_core()`.
+    ///
+    /// The returned core keeps the environment alive.
+    pub fn get_core(&self) -> Result<SharedCore> {
+        let core = self.environment.get_core()?;
+
+        Ok(SharedCore {
+            // The environment is kept alive for as long as the core is around.
+            core: unsafe { CoreRef::from_ptr(core.ptr()) },
+            environment: self.clone(),
+        })
+    }
+
+    /// Wraps a node of this environment in a `SharedNode`.
+    #[inline]
+    fn shared_node(&self, node: Node) -> SharedNode {
+        SharedNode {
+            // The environment is kept alive for as long as the node is around.
+            node: unsafe { Node::from_ptr(node.into_ptr()) },
+            environment: self.clone(),
+        }
+    }
+
+    /// Drops this handle from a VapourSynth callback.
+    ///
+    /// If this is the last handle, the environment is freed on a new thread, since freeing it
+    /// waits for the thread running the callback.
+    fn drop_from_callback(self) {
+        if let Some(environment) = Arc::into_inner(self.environment) {
+            thread::spawn(move || drop(environment));
+        }
+    }
+}
+
+impl SharedNode {
+    /// Returns the node.
+    ///
+    /// The node is bound to the lifetime of this handle.
+    #[inline]
+    pub fn node(&self) -> &Node<'_> {
+        &self.node
+    }
+
+    /// Returns the environment this node belongs to.
+    #[inline]
+    pub fn environment(&self) -> &SharedEnvironment {
+        &self.environment
+    }
+
+    /// Generates a frame directly, like `Node::get_frame()`.
+    ///
+    /// # Panics
+    /// Panics is `n` is greater than `i32::MAX`.
+    pub fn get_frame(&self, n: usize) -> std::result::Result<SharedFrame, GetFrameError<'static>> {
+        let frame = self.node.get_frame(n)?;
+
+        Ok(SharedFrame {
+            frame,
+            environment: self.environment.clone(),
+        })
+    }
+
+    /// Requests the generation of a frame, like `Node::get_frame_async()`.
+    ///
+    /// The callback doesn't need to outlive the environment, since the request keeps it alive.
+    /// Handles dropped by the callback never free the environment on the VapourSynth thread
+    /// running it.
+    ///
+    /// # Panics
+    /// Panics is `n` is greater than `i32::MAX`.
+    pub fn get_frame_async<F>(&self, n: usize, callback: F)
+    where
+        F: FnOnce(std::result::Result<SharedFrame, GetFrameError>, usize, SharedNode)
+            + Send
+            + 'static,
+    {
+        let environment = self.environment.clone();
+        let guard = environment.requests.start();
+
+        self.node.get_frame_async(n, move |frame, n, node| {
+            let frame = frame.map(|frame| SharedFrame {
+                frame,
+                environment: environment.clone(),
+            });
+            let node = SharedNode {
+                node,
+                environment: environment.clone(),
+            };
+
+            // The callback can't drop the last handle, since this one is still around.
+            callback(frame, n, node);
+
+            environment.drop_from_callback();
+            drop(guard);
+        });
+    }
+}
+
+impl SharedFrame {
+    /// Returns the frame.
+    ///
+    /// The frame is bound to the lifetime of this handle.
+    #[inline]
+    pub fn frame(&self) -> &FrameRef<'_> {
+        &self.frame
+    }
+
+    /// Returns the environment this frame belongs to.
+    #[inline]
+    pub fn environment(&self) -> &SharedEnvironment {
+        &self.environment
+    }
+}
+
+impl SharedCore {
+    /// Returns the core.
+    ///
+    /// The core is bound to the lifetime of this handle.
+    #[inline]
+    pub fn core(&self) -> CoreRef<'_> {
+        self.core
+    }
+
+    /// Returns the environment this core belongs to.
+    #[inline]
+    pub fn environment(&self) -> &SharedEnvironment {
+        &self.environment
+    }
+}
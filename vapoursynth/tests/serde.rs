@@ -0,0 +1,90 @@
+//! Serde support tests.
+//!
+//! These live outside of the library tests because `serde_json` adds `PartialEq` impls for
+//! primitive types, which breaks type inference in the library code compiled for its tests.
+#![cfg(feature = "serde")]
+
+mod need_api {
+    use serde::Deserialize;
+    use serde_json::json;
+    use vapoursynth::map;
+    use vapoursynth::prelude::*;
+
+    #[test]
+    fn map_serde() {
+        let mut map = OwnedMap::new(API::get().unwrap());
+        assert_eq!(map.set_int("width", 1920), Ok(()));
+        assert_eq!(map.set_float("gamma", 2.5), Ok(()));
+        assert_eq!(map.set_str("kernel", "spline36"), Ok(()));
+        assert_eq!(map.set_int_array("planes", &[0, 1]), Ok(()));
+
+        let value = serde_json::to_value(&map).unwrap();
+        assert_eq!(
+            value,
+            json!({ "width": 1920, "gamma": 2.5, "kernel": "spline36", "planes": [0, 1] })
+        );
+
+        let other: OwnedMap = serde_json::from_value(value).unwrap();
+        assert_eq!(map, other);
+
+        let other: OwnedMap =
+            serde_json::from_value(json!({ "flag": true, "empty": [], "values": [1, 2, 3] }))
+                .unwrap();
+        assert_eq!(other.get_int("flag"), Ok(1));
+        assert_eq!(other.get_int_array("values"), Ok(&[1, 2, 3][..]));
+        assert_eq!(other.value_count("empty"), Err(map::Error::KeyNotFound));
+
+        assert!(serde_json::from_value::<OwnedMap>(json!({ "mixed": [1, "a"] })).is_err());
+        assert!(serde_json::from_value::<OwnedMap>(json!({ "nested": [[1]] })).is_err());
+        assert!(serde_json::from_value::<OwnedMap>(json!({ "in valid": 1 })).is_err());
+
+        assert_eq!(map.set_data("blob", &[1, 2]), Ok(()));
+        assert_eq!(serde_json::to_value(&map).unwrap()["blob"], json!([1, 2]));
+
+        // JSON has no byte strings, so the data comes back as integers.
+        let other: OwnedMap = serde_json::from_value(serde_json::to_value(&map).unwrap()).unwrap();
+        assert_eq!(other.value_type("blob"), Ok(ValueType::Int));
+        assert_eq!(other.get_int_array("blob"), Ok(&[1, 2][..]));
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum Kernel {
+            Bicubic,
+            Spline36,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Args<'a> {
+            width: u16,
+            gamma: f32,
+            kernel: Kernel,
+            planes: Vec<u8>,
+            #[serde(rename = "blob")]
+            data: &'a [u8],
+            title: Option<String>,
+            #[serde(default)]
+            crop: Vec<i64>,
+        }
+
+        let args: Args = map::from_map(&map).unwrap();
+        assert_eq!(
+            args,
+            Args {
+                width: 1920,
+                gamma: 2.5,
+                kernel: Kernel::Spline36,
+                planes: vec![0, 1],
+                data: &[1, 2],
+                title: None,
+                crop: vec![],
+            }
+        );
+
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Planes {
+            planes: i64,
+        }
+        assert!(map::from_map::<Planes>(&map).is_err());
+    }
+}